@@ -21,15 +21,56 @@ use zellij_tile::prelude::*;
 
 const DEFAULT_DELAY_SECS: f64 = 0.4;
 const DEFAULT_MAX_HEIGHT_PCT: usize = 40;
+const DEFAULT_LEARN_THRESHOLD: u64 = 50;
+/// A learned mode's delay never grows past this multiple of `delay_secs`.
+const LEARN_MAX_DELAY_FACTOR: f64 = 4.0;
+/// Per-mode entry counts for the learning popup.
+const LEARNING_PATH: &str = "/data/learning";
+/// Recorded mode switches for the browser's usage views, and the header
+/// (format name and version) its first line must match.
+const USAGE_PATH: &str = "/data/usage_stats";
+const USAGE_HEADER: &str = "zj-which-key-usage 1";
+/// How long after leaving a mode for the base mode a tab or pane change is
+/// still credited to that mode.
+const USAGE_EXIT_SECS: u64 = 1;
+const DAY_SECS: u64 = 24 * 60 * 60;
+/// The "unused" view lists mode switches not seen for this long.
+const UNUSED_AFTER_SECS: u64 = 30 * DAY_SECS;
+/// Pipe message name that clears the learning counts.
+const PIPE_RESET_LEARNING: &str = "which-key-reset-learning";
+/// Popup → controller: the popup's plugin id (payload), so mode path updates
+/// can be addressed to it.
+const PIPE_POPUP_READY: &str = "which-key-popup-ready";
+/// Controller → popup: the mode path since leaving base mode (payload,
+/// comma-separated mode names).
+const PIPE_MODE_PATH: &str = "which-key-mode-path";
 
 /// Margin between the popup and the screen edge, in cells.
 const MARGIN: usize = 1;
+/// Columns around a box's content: the margins, the border (2) and one cell
+/// of padding on each side (2).
+const BOX_FRAME_COLS: usize = 2 * MARGIN + 4;
 /// Hard cap on the popup's inner content width.
 const MAX_INNER_WIDTH: usize = 64;
 /// Hard cap on the width of the keys column.
 const KEYS_COL_MAX: usize = 18;
 /// Smallest pane we'll ever ask for (border included).
 const MIN_BOX_ROWS: usize = 4;
+/// Default most entry rows for `layout "strip"`.
+const DEFAULT_STRIP_ROWS: usize = 3;
+/// Between entries on a strip row.
+const STRIP_SEP: &str = " · ";
+/// How long a strip that doesn't fit shows each page.
+const STRIP_PAGE_SECS: f64 = 4.0;
+/// Where the browser keeps its query history (the plugin's `/data` directory
+/// is shared by every instance of the plugin and survives restarts).
+const HISTORY_PATH: &str = "/data/query_history";
+/// Most queries the history keeps.
+const HISTORY_MAX: usize = 100;
+/// Browser width at which the detail pane sits beside the list, not below it.
+const DETAIL_SIDE_MIN_COLS: usize = 100;
+/// First browser line holding a list row (below the search prompt and a blank).
+const BROWSER_BODY_TOP: usize = 2;
 
 #[derive(Default, PartialEq, Clone, Copy)]
 enum Role {
@@ -38,6 +79,8 @@ enum Role {
     Popup,
     /// A focused, searchable view of every binding in every mode.
     Browser,
+    /// A resident tiled pane from a layout showing the current mode's keys.
+    Panel,
 }
 
 #[derive(Default, PartialEq, Clone, Copy)]
@@ -47,31 +90,202 @@ enum Position {
     BottomLeft,
 }
 
+/// The popup's shape: a corner box with one entry per row, or a full-width
+/// strip that flows entries across a few rows.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum PopupLayout {
+    #[default]
+    Box,
+    Strip,
+}
+
+/// The area the popup docks into: the whole display, or the focused pane.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum Anchor {
+    #[default]
+    Display,
+    FocusedPane,
+}
+
+/// What the browser lists: every binding, or (from the recorded usage
+/// statistics) the most used mode switches, or ones not used lately.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum BrowserView {
+    #[default]
+    All,
+    MostUsed,
+    Unused,
+}
+
+impl BrowserView {
+    fn next(self) -> Self {
+        match self {
+            BrowserView::All => BrowserView::MostUsed,
+            BrowserView::MostUsed => BrowserView::Unused,
+            BrowserView::Unused => BrowserView::All,
+        }
+    }
+}
+
+/// Key scheme for the browser: always typing into the query, or vim-style
+/// insert/normal modes.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum BrowserKeys {
+    #[default]
+    Default,
+    Vim,
+}
+
+/// How the popup treats globals (bindings also present in the base mode):
+/// left out, mixed in with the mode's own keys, or listed in a dimmed
+/// trailing section.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum Globals {
+    #[default]
+    Hide,
+    Show,
+    Section,
+}
+
+/// Popup entry order: the fixed priority table, or most used first.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum SortOrder {
+    #[default]
+    Priority,
+    Usage,
+}
+
+/// What the popup does with entries that can't do anything right now (closing
+/// the only tab, focusing a pane that isn't there): grey them out, leave them
+/// out, or show them like the rest.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum Inapplicable {
+    #[default]
+    Dim,
+    Hide,
+    Show,
+}
+
+/// How the controller reacts to modes you've entered often: not at all, by
+/// stretching the popup delay, or by not showing the popup once learned.
+#[derive(Default, PartialEq, Clone, Copy)]
+enum Learn {
+    #[default]
+    Off,
+    Delay,
+    Hide,
+}
+
 #[derive(Default)]
 struct State {
     role: Role,
     position: Position,
+    globals: Globals,
+    sort: SortOrder,
+    browser_keys: BrowserKeys,
     mode_info: ModeInfo,
 
     auto_show: bool,
     delay_secs: f64,
     max_height_pct: usize,
+    learn: Learn,
+    learn_threshold: u64,
+    /// Which bindings become entries and in what order (noise, priority
+    /// overrides, pins).
+    grouping: Grouping,
+    /// One-line hints shown under the popup title, from `desc_<mode>` config
+    /// keys, keyed by `mode_key`.
+    mode_descriptions: BTreeMap<String, String>,
 
     permissions_granted: bool,
     own_id: u32,
 
     /// Controller: whether a popup instance is currently alive.
     popup_visible: bool,
+    /// Controller: how often each mode has been entered, persisted in `/data`.
+    learning: Learning,
+    /// Controller: record mode switches, and the actions inferred from tab
+    /// and pane changes, into `usage` (`track_usage "true"`). `sort "usage"`
+    /// only reads what was recorded.
+    track_usage: bool,
+    /// Recorded mode switches and actions, persisted in `/data` by the
+    /// controller; the popup gets a snapshot of the counts on spawn.
+    usage: UsageStats,
+    /// Controller: the tabs and panes as last seen, to infer actions from
+    /// what changed, and the mode just left for the base mode (with when),
+    /// which gets the credit for changes that land right after. The
+    /// on-demand cheat sheet watches them (and whether its own pane has
+    /// focus) to close on the next thing you do.
+    tabs_seen: Option<TabsSeen>,
+    panes_seen: Option<PanesSeen>,
+    usage_exit: Option<(InputMode, u64)>,
+    own_focused: Option<bool>,
     /// Display area of the focused tab, learned from `TabUpdate`.
     display_rows: usize,
     display_cols: usize,
+    /// Where the popup docks (`anchor`).
+    anchor: Anchor,
+    /// In the base mode (the panel, or a popup opened by keybind), show the
+    /// one-line `base_summary` of keys into each mode instead of a list.
+    base_summary: bool,
+    /// The popup's shape (`layout`), and the most rows a strip may take.
+    popup_layout: PopupLayout,
+    strip_rows: usize,
+    /// Popup / panel: the strip page on show, how many pages the last render
+    /// had, when (in `now_secs_f64` time, 0 if not yet counting) the page
+    /// turns, and whether a timer for that is pending.
+    strip_page: usize,
+    strip_pages: usize,
+    strip_turn_at: f64,
+    strip_timer: bool,
+    /// The latest tabs and panes, for the popup's context line, toggle
+    /// states and placement.
+    tabs: Vec<TabInfo>,
+    pane_manifest: PaneManifest,
+    /// Popup: show a context line (tab, panes, sync/fullscreen/floating)
+    /// under the title, and the on/off state of those toggles.
+    show_context: bool,
+    /// Popup: how to treat entries the focused tab makes pointless.
+    inapplicable: Inapplicable,
+    /// Popup: label the collapsed "Go to tab N" row with the tabs' names.
+    tab_names: bool,
+    /// Popup: fold "Focus Left/Down/Up/Right" and the like into one row.
+    compact_directions: bool,
+    /// Popup: how many of a mode's bindings to preview under entries that
+    /// switch to it; 0 turns previews off.
+    preview_entries: usize,
+    /// Modes entered since leaving base mode, ending with the current one.
+    /// Kept by the controller and handed to the popup for its breadcrumb.
+    mode_path: Vec<InputMode>,
+    /// Controller: the open popup's plugin id, once it has reported in.
+    popup_id: Option<u32>,
+    /// Popup: the controller's plugin id, to report our own id to.
+    controller_id: Option<u32>,
     /// Popup: the last coordinates we asked for, to avoid redundant resizes.
     last_coords: Option<(usize, usize, usize, usize)>,
 
-    /// Browser: the current fuzzy-search query.
-    query: String,
+    /// Browser: the current fuzzy-search query, with its cursor.
+    query: LineEditor,
+    /// Browser: past queries, persisted across launches.
+    history: QueryHistory,
+    /// Browser (vim keys): in normal mode rather than typing into the query,
+    /// and whether a `g` is waiting for its second `g`.
+    vim_normal: bool,
+    pending_g: bool,
+    /// Browser: Esc / Enter asked to close; `update_browser` does the closing
+    /// so the key handlers stay free of host calls.
+    closing: bool,
     /// Browser: index of the highlighted row in the filtered list.
     selected: usize,
+    /// Browser: whether the detail pane for the selected row is open.
+    show_detail: bool,
+    /// Browser: which list is shown.
+    browser_view: BrowserView,
+    /// Browser: geometry of the last render (first visible row, body height,
+    /// list width), used for paging and mapping mouse clicks to rows.
+    browser_scroll: usize,
+    browser_page: usize,
+    browser_list_cols: usize,
 }
 
 register_plugin!(State);
@@ -88,14 +302,23 @@ impl ZellijPlugin for State {
                 request_permission(&[
                     PermissionType::ReadApplicationState,
                     PermissionType::ChangeApplicationState,
+                    PermissionType::MessageAndLaunchOtherPlugins,
                 ]);
                 subscribe(&[
                     EventType::ModeUpdate,
                     EventType::TabUpdate,
+                    EventType::PaneUpdate,
+                    EventType::Timer,
                     EventType::PermissionRequestResult,
                 ]);
             }
             Role::Controller => {
+                if self.learn != Learn::Off {
+                    self.learning = Learning::load();
+                }
+                if self.track_usage || self.sort == SortOrder::Usage {
+                    self.usage = UsageStats::load();
+                }
                 request_permission(&[
                     PermissionType::ReadApplicationState,
                     PermissionType::ChangeApplicationState,
@@ -104,11 +327,14 @@ impl ZellijPlugin for State {
                 subscribe(&[
                     EventType::ModeUpdate,
                     EventType::TabUpdate,
+                    EventType::PaneUpdate,
                     EventType::Timer,
                     EventType::PermissionRequestResult,
                 ]);
             }
             Role::Browser => {
+                self.history = QueryHistory::load();
+                self.usage = UsageStats::load();
                 request_permission(&[
                     PermissionType::ReadApplicationState,
                     PermissionType::ChangeApplicationState,
@@ -116,6 +342,22 @@ impl ZellijPlugin for State {
                 subscribe(&[
                     EventType::ModeUpdate,
                     EventType::Key,
+                    EventType::Mouse,
+                    EventType::PermissionRequestResult,
+                ]);
+            }
+            Role::Panel => {
+                if self.sort == SortOrder::Usage {
+                    self.usage = UsageStats::load();
+                }
+                // A fixture of the layout: never take focus from real panes.
+                set_selectable(false);
+                request_permission(&[PermissionType::ReadApplicationState]);
+                subscribe(&[
+                    EventType::ModeUpdate,
+                    EventType::TabUpdate,
+                    EventType::PaneUpdate,
+                    EventType::Timer,
                     EventType::PermissionRequestResult,
                 ]);
             }
@@ -127,6 +369,15 @@ impl ZellijPlugin for State {
             Role::Popup => self.update_popup(event),
             Role::Controller => self.update_controller(event),
             Role::Browser => self.update_browser(event),
+            Role::Panel => self.update_panel(event),
+        }
+    }
+
+    fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
+        match self.role {
+            Role::Controller => self.pipe_controller(pipe_message),
+            Role::Popup => self.pipe_popup(pipe_message),
+            _ => false,
         }
     }
 
@@ -134,6 +385,7 @@ impl ZellijPlugin for State {
         match self.role {
             Role::Popup => self.render_popup(rows, cols),
             Role::Browser => self.render_browser(rows, cols),
+            Role::Panel => self.render_panel(rows, cols),
             Role::Controller => {}
         }
     }
@@ -144,12 +396,52 @@ impl State {
         self.role = match config.get("role").map(String::as_str) {
             Some("popup") => Role::Popup,
             Some("browser") => Role::Browser,
+            Some("panel") => Role::Panel,
             _ => Role::Controller,
         };
         self.position = match config.get("position").map(String::as_str) {
             Some("bottom-left") => Position::BottomLeft,
             _ => Position::BottomRight,
         };
+        self.base_summary = config
+            .get("base_summary")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        self.popup_layout = match config.get("layout").map(String::as_str) {
+            Some("strip") => PopupLayout::Strip,
+            _ => PopupLayout::Box,
+        };
+        self.strip_rows = config
+            .get("strip_rows")
+            .and_then(|s| s.parse().ok())
+            .filter(|r| *r > 0)
+            .unwrap_or(DEFAULT_STRIP_ROWS);
+        self.anchor = match config.get("anchor").map(String::as_str) {
+            Some("focused-pane") => Anchor::FocusedPane,
+            _ => Anchor::Display,
+        };
+        self.globals = match config.get("globals").map(String::as_str) {
+            Some("show") => Globals::Show,
+            Some("section") => Globals::Section,
+            _ => Globals::Hide,
+        };
+        self.inapplicable = match config.get("inapplicable").map(String::as_str) {
+            Some("hide") => Inapplicable::Hide,
+            Some("show") => Inapplicable::Show,
+            _ => Inapplicable::Dim,
+        };
+        self.sort = match config.get("sort").map(String::as_str) {
+            Some("usage") => SortOrder::Usage,
+            _ => SortOrder::Priority,
+        };
+        // The popup gets the controller's counts with its config.
+        if let Some(counts) = config.get("usage_counts") {
+            self.usage = parse_usage_counts(counts);
+        }
+        self.browser_keys = match config.get("browser_keys").map(String::as_str) {
+            Some("vim") => BrowserKeys::Vim,
+            _ => BrowserKeys::Default,
+        };
         self.auto_show = config
             .get("auto_show")
             .map(|s| s == "true")
@@ -163,6 +455,68 @@ impl State {
             .and_then(|s| s.parse().ok())
             .filter(|p| *p > 0 && *p <= 100)
             .unwrap_or(DEFAULT_MAX_HEIGHT_PCT);
+        self.mode_path = config
+            .get("mode_path")
+            .map(|s| parse_mode_path(s))
+            .unwrap_or_default();
+        self.controller_id = config.get("controller_id").and_then(|s| s.parse().ok());
+        self.preview_entries = config
+            .get("preview_entries")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        self.learn = match config.get("learn").map(String::as_str) {
+            Some("delay") => Learn::Delay,
+            Some("hide") => Learn::Hide,
+            _ => Learn::Off,
+        };
+        self.learn_threshold = config
+            .get("learn_threshold")
+            .and_then(|s| s.parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(DEFAULT_LEARN_THRESHOLD);
+        self.track_usage = config
+            .get("track_usage")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        self.show_context = config
+            .get("show_context")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        self.tab_names = config
+            .get("tab_names")
+            .map(|s| s == "true")
+            .unwrap_or(false);
+        self.compact_directions = config
+            .get("compact_directions")
+            .map(|s| s != "false")
+            .unwrap_or(true);
+        self.mode_descriptions = config
+            .iter()
+            .filter_map(|(key, value)| {
+                Some((key.strip_prefix("desc_")?.to_string(), value.clone()))
+            })
+            .collect();
+        self.grouping = Grouping {
+            show_noise: config
+                .get("show_noise")
+                .map(|s| s == "true")
+                .unwrap_or(false),
+            priorities: config
+                .get("priority")
+                .map(|s| parse_priorities(s))
+                .unwrap_or_default(),
+            pins: config.get("pin").map(|s| parse_list(s)).unwrap_or_default(),
+            hide_actions: config
+                .get("hide_actions")
+                .map(|s| parse_scoped(s))
+                .unwrap_or_default(),
+            hide_keys: config
+                .get("hide_keys")
+                .map(|s| parse_scoped(s))
+                .unwrap_or_default(),
+            // The browser keeps hidden bindings (dimmed) so nothing is lost.
+            keep_hidden: self.role == Role::Browser,
+        };
     }
 
     fn base_mode(&self) -> InputMode {
@@ -182,25 +536,62 @@ impl State {
             }
             Event::TabUpdate(tabs) => {
                 self.update_display_area(&tabs);
+                self.tabs = tabs;
+                if self.track_usage {
+                    let seen = TabsSeen::of(&self.tabs);
+                    let before = std::mem::replace(&mut self.tabs_seen, seen);
+                    if let (Some(before), Some(after)) = (before, seen) {
+                        self.record_actions(&tab_actions(&before, &after));
+                    }
+                }
+            }
+            Event::PaneUpdate(manifest) => {
+                self.pane_manifest = manifest;
+                if self.track_usage {
+                    let seen = PanesSeen::of(&self.tabs, &self.pane_manifest);
+                    let before = std::mem::replace(&mut self.panes_seen, seen);
+                    if let (Some(before), Some(after)) = (before, seen) {
+                        self.record_actions(&pane_actions(&before, &after));
+                    }
+                }
             }
             Event::ModeUpdate(mode_info) => {
+                // Before the first update there's no real previous mode to
+                // count a switch from.
+                let previous = (!self.mode_info.keybinds.is_empty()).then_some(self.mode_info.mode);
                 self.mode_info = mode_info;
+                let switched_from = previous.filter(|p| *p != self.mode_info.mode);
+                if let Some(from) = switched_from {
+                    let base = self.base_mode();
+                    advance_mode_path(&mut self.mode_path, self.mode_info.mode, base);
+                    self.send_mode_path();
+                    if self.learn != Learn::Off && !self.is_base_mode() {
+                        self.learning.record(self.mode_info.mode);
+                    }
+                    if self.track_usage {
+                        let now = now_secs();
+                        self.usage.record(from, self.mode_info.mode, now);
+                        self.usage_exit = self.is_base_mode().then_some((from, now));
+                    }
+                }
                 if !self.permissions_granted || !self.auto_show {
                     return false;
                 }
                 if self.is_base_mode() {
                     // The popup closes itself on base mode; just track that.
                     self.popup_visible = false;
-                } else if !self.popup_visible {
+                    self.popup_id = None;
+                } else if !self.popup_visible && !self.learned_away() {
                     // Arm the idle delay; we spawn when the timer fires.
-                    set_timeout(self.delay_secs);
+                    set_timeout(self.effective_delay());
                 }
             }
             Event::Timer(_)
                 if self.permissions_granted
                     && self.auto_show
                     && !self.is_base_mode()
-                    && !self.popup_visible =>
+                    && !self.popup_visible
+                    && !self.learned_away() =>
             {
                 self.spawn_popup();
                 self.popup_visible = true;
@@ -210,6 +601,67 @@ impl State {
         false
     }
 
+    /// Credit inferred actions to the current mode, or outside one to the
+    /// mode just left: its key acted and then returned to the base mode, and
+    /// the tab or pane update can arrive after the mode switch. Changes made
+    /// in the base mode itself (global keys, the mouse) aren't counted.
+    fn record_actions(&mut self, actions: &[&str]) {
+        if actions.is_empty() {
+            return;
+        }
+        let now = now_secs();
+        let mode = if self.is_base_mode() {
+            self.usage_exit
+                .take()
+                .filter(|(_, at)| now.saturating_sub(*at) <= USAGE_EXIT_SECS)
+                .map(|(mode, _)| mode)
+        } else {
+            Some(self.mode_info.mode)
+        };
+        if let Some(mode) = mode {
+            self.usage.record_actions(mode, actions, now);
+        }
+    }
+
+    fn pipe_controller(&mut self, pipe_message: PipeMessage) -> bool {
+        if pipe_message.name == PIPE_RESET_LEARNING {
+            self.learning.reset();
+        } else if pipe_message.name == PIPE_POPUP_READY && self.popup_visible {
+            self.popup_id = pipe_message.payload.and_then(|p| p.parse().ok());
+        }
+        false
+    }
+
+    /// Tell the open popup the current mode path, for its breadcrumb.
+    fn send_mode_path(&self) {
+        let Some(popup_id) = self.popup_id else {
+            return;
+        };
+        pipe_message_to_plugin(
+            MessageToPlugin::new(PIPE_MODE_PATH)
+                .with_destination_plugin_id(popup_id)
+                .with_payload(format_mode_path(&self.mode_path)),
+        );
+    }
+
+    /// Entries into the current mode so far.
+    fn mode_uses(&self) -> u64 {
+        self.learning.count(self.mode_info.mode)
+    }
+
+    /// Whether `learn "hide"` has retired the popup for the current mode.
+    fn learned_away(&self) -> bool {
+        self.learn == Learn::Hide && self.mode_uses() >= self.learn_threshold
+    }
+
+    /// The idle delay for the current mode, stretched under `learn "delay"`.
+    fn effective_delay(&self) -> f64 {
+        match self.learn {
+            Learn::Delay => learned_delay(self.delay_secs, self.mode_uses(), self.learn_threshold),
+            _ => self.delay_secs,
+        }
+    }
+
     fn spawn_popup(&self) {
         let mut config = BTreeMap::new();
         config.insert("role".to_string(), "popup".to_string());
@@ -217,6 +669,69 @@ impl State {
             "max_height_pct".to_string(),
             self.max_height_pct.to_string(),
         );
+        config.insert(
+            "show_noise".to_string(),
+            self.grouping.show_noise.to_string(),
+        );
+        if !self.grouping.priorities.is_empty() {
+            let priorities: Vec<String> = self
+                .grouping
+                .priorities
+                .iter()
+                .map(|(name, priority)| format!("{}={}", name, priority))
+                .collect();
+            config.insert("priority".to_string(), priorities.join(","));
+        }
+        if !self.grouping.pins.is_empty() {
+            config.insert("pin".to_string(), self.grouping.pins.join(","));
+        }
+        if !self.grouping.hide_actions.is_empty() {
+            config.insert(
+                "hide_actions".to_string(),
+                format_scoped(&self.grouping.hide_actions),
+            );
+        }
+        config.insert("show_context".to_string(), self.show_context.to_string());
+        config.insert("mode_path".to_string(), format_mode_path(&self.mode_path));
+        config.insert("controller_id".to_string(), self.own_id.to_string());
+        config.insert("tab_names".to_string(), self.tab_names.to_string());
+        config.insert(
+            "compact_directions".to_string(),
+            self.compact_directions.to_string(),
+        );
+        config.insert(
+            "preview_entries".to_string(),
+            self.preview_entries.to_string(),
+        );
+        for (mode, desc) in &self.mode_descriptions {
+            config.insert(format!("desc_{}", mode), desc.clone());
+        }
+        if !self.grouping.hide_keys.is_empty() {
+            config.insert(
+                "hide_keys".to_string(),
+                format_scoped(&self.grouping.hide_keys),
+            );
+        }
+        config.insert(
+            "globals".to_string(),
+            match self.globals {
+                Globals::Hide => "hide".to_string(),
+                Globals::Show => "show".to_string(),
+                Globals::Section => "section".to_string(),
+            },
+        );
+        config.insert(
+            "inapplicable".to_string(),
+            match self.inapplicable {
+                Inapplicable::Dim => "dim".to_string(),
+                Inapplicable::Hide => "hide".to_string(),
+                Inapplicable::Show => "show".to_string(),
+            },
+        );
+        if self.sort == SortOrder::Usage {
+            config.insert("sort".to_string(), "usage".to_string());
+            config.insert("usage_counts".to_string(), format_usage_counts(&self.usage));
+        }
         config.insert(
             "position".to_string(),
             match self.position {
@@ -224,6 +739,13 @@ impl State {
                 Position::BottomRight => "bottom-right".to_string(),
             },
         );
+        if self.anchor == Anchor::FocusedPane {
+            config.insert("anchor".to_string(), "focused-pane".to_string());
+        }
+        if self.popup_layout == PopupLayout::Strip {
+            config.insert("layout".to_string(), "strip".to_string());
+        }
+        config.insert("strip_rows".to_string(), self.strip_rows.to_string());
 
         let mut message = MessageToPlugin::new("spawn_popup")
             .with_plugin_url("zellij:OWN_URL")
@@ -243,28 +765,149 @@ impl State {
             Event::PermissionRequestResult(PermissionStatus::Granted) => {
                 self.permissions_granted = true;
                 set_selectable(false);
+                // Ask the controller for mode path updates.
+                if let Some(controller_id) = self.controller_id {
+                    pipe_message_to_plugin(
+                        MessageToPlugin::new(PIPE_POPUP_READY)
+                            .with_destination_plugin_id(controller_id)
+                            .with_payload(self.own_id.to_string()),
+                    );
+                }
                 false
             }
             Event::TabUpdate(tabs) => {
                 self.update_display_area(&tabs);
+                self.tabs = tabs;
+                if self.cheat_sheet_used() {
+                    close_self();
+                    return false;
+                }
+                // Toggle labels and the context line follow the tab.
                 self.reposition();
-                false
+                true
+            }
+            Event::PaneUpdate(manifest) => {
+                self.pane_manifest = manifest;
+                if self.cheat_sheet_used() {
+                    close_self();
+                    return false;
+                }
+                self.reposition();
+                true
             }
             Event::ModeUpdate(mode_info) => {
                 let was_base = self.is_base_mode();
+                let first = self.mode_info.keybinds.is_empty();
                 self.mode_info = mode_info;
+                let base = self.base_mode();
+                advance_mode_path(&mut self.mode_path, self.mode_info.mode, base);
                 if !was_base && self.is_base_mode() {
                     // Returned to base mode: vanish.
                     close_self();
                     return false;
                 }
+                if self.on_demand_summary() && !first {
+                    // An on-demand cheat sheet is done with the next key
+                    // zellij reports; one that enters a mode leaves it to
+                    // the controller's popup.
+                    close_self();
+                    return false;
+                }
+                self.restart_strip_pages();
                 self.reposition();
                 true
             }
+            Event::Timer(_) => self.turn_strip_page(),
             _ => false,
         }
     }
 
+    /// A cheat sheet opened by keybind rather than by the controller.
+    fn on_demand_summary(&self) -> bool {
+        self.base_summary && self.controller_id.is_none()
+    }
+
+    /// Whether, since the on-demand cheat sheet opened, the tabs or panes
+    /// changed the way a base-mode key changes them, or its own pane took
+    /// focus again (its key pressed a second time). The popup showing up
+    /// doesn't count.
+    fn cheat_sheet_used(&mut self) -> bool {
+        if !self.on_demand_summary() {
+            return false;
+        }
+        let tabs = TabsSeen::of(&self.tabs);
+        let panes = PanesSeen::of(&self.tabs, &self.pane_manifest);
+        let own_focused = self
+            .pane_manifest
+            .panes
+            .values()
+            .flatten()
+            .find(|p| p.is_plugin && p.id == self.own_id)
+            .map(|p| p.is_focused);
+        let tabs_before = std::mem::replace(&mut self.tabs_seen, tabs);
+        let panes_before = std::mem::replace(&mut self.panes_seen, panes);
+        let own_before = std::mem::replace(&mut self.own_focused, own_focused);
+        let tab_acted = matches!(
+            (tabs_before, tabs),
+            (Some(b), Some(a)) if !tab_actions(&b, &a).is_empty()
+        );
+        let pane_acted = matches!(
+            (panes_before, panes),
+            (Some(b), Some(a)) if !pane_actions(&b, &a).is_empty()
+        );
+        tab_acted || pane_acted || (own_before == Some(false) && own_focused == Some(true))
+    }
+
+    /// A new mode starts on its first strip page, with a full countdown.
+    fn restart_strip_pages(&mut self) {
+        self.strip_page = 0;
+        self.strip_turn_at = 0.0;
+    }
+
+    /// After a render, start counting down to the next page if the strip
+    /// has more than one. Timers can't be cancelled, so a pending one is
+    /// left to fire and re-armed for whatever time is left.
+    fn arm_strip_timer(&mut self, pages: usize) {
+        self.strip_pages = pages;
+        if pages < 2 {
+            return;
+        }
+        let now = now_secs_f64();
+        if self.strip_turn_at == 0.0 {
+            self.strip_turn_at = now + STRIP_PAGE_SECS;
+        }
+        if !self.strip_timer {
+            set_timeout((self.strip_turn_at - now).max(0.0));
+            self.strip_timer = true;
+        }
+    }
+
+    fn turn_strip_page(&mut self) -> bool {
+        self.strip_timer = false;
+        if self.strip_pages < 2 {
+            return false;
+        }
+        if now_secs_f64() + 0.05 < self.strip_turn_at {
+            // The countdown restarted since this timer was set.
+            self.arm_strip_timer(self.strip_pages);
+            return false;
+        }
+        self.strip_page += 1;
+        self.strip_turn_at = 0.0;
+        true
+    }
+
+    /// The controller's mode path replaces ours; between messages we follow
+    /// `ModeUpdate`s ourselves, so a switch made while we were loading still
+    /// shows up.
+    fn pipe_popup(&mut self, pipe_message: PipeMessage) -> bool {
+        if pipe_message.name != PIPE_MODE_PATH {
+            return false;
+        }
+        self.mode_path = parse_mode_path(pipe_message.payload.as_deref().unwrap_or(""));
+        true
+    }
+
     /// Resize/move ourselves to hug the current content in the chosen corner.
     /// No-op when the target box is unchanged, so mode/tab churn stays quiet.
     fn reposition(&mut self) {
@@ -282,21 +925,80 @@ impl State {
     }
 
     fn render_popup(&mut self, pane_rows: usize, pane_cols: usize) {
-        // Prefer the known display area; fall back to our pane size before the
+        // Prefer the known anchor area; fall back to our pane size before the
         // first TabUpdate arrives.
-        let display_cols = if self.display_cols > 0 {
-            self.display_cols
-        } else {
-            pane_cols + 2
-        };
-        let display_rows = if self.display_rows > 0 {
-            self.display_rows
-        } else {
-            pane_rows + 2
-        };
+        let (_, _, display_cols, display_rows) =
+            self.anchor_area()
+                .unwrap_or((0, 0, pane_cols + 2, pane_rows + 2));
+        let (lines, pages) = self.hint_lines(
+            display_cols,
+            display_rows,
+            self.max_height_pct,
+            self.strip_rows,
+        );
+        for line in lines {
+            println!("{}", line);
+        }
+        self.arm_strip_timer(pages);
+    }
 
+    /// The heading and entries as styled lines, laid out for a
+    /// `display_cols` x `display_rows` area as the popup would size itself
+    /// for it, and how many strip pages there are (1 outside the strip).
+    fn hint_lines(
+        &self,
+        display_cols: usize,
+        display_rows: usize,
+        max_height_pct: usize,
+        strip_rows: usize,
+    ) -> (Vec<String>, usize) {
+        let heading = self.popup_heading();
+        let mut lines = Vec::new();
+        if let Some(summary) = self.summary() {
+            let (_, _, summary) = summary_box(&summary, heading.len(), display_cols, display_rows);
+            let header = Colour::Fixed(252).bold();
+            let text = Colour::Fixed(250).normal();
+            let dim = Colour::Fixed(244).normal();
+            let width = inner_width(display_cols);
+            for (i, line) in heading.iter().enumerate() {
+                let line = truncate_to_width(line, width);
+                let style = if i == 0 { header } else { dim };
+                lines.push(style.paint(line).to_string());
+            }
+            for line in summary {
+                lines.push(text.paint(line).to_string());
+            }
+            return (lines, 1);
+        }
         let entries = self.entries();
-        let layout = compute_layout(&entries, display_cols, display_rows, self.max_height_pct);
+        let globals = self.global_entries();
+        if self.popup_layout == PopupLayout::Strip {
+            let starts = strip_pages(
+                &entries,
+                globals.len(),
+                heading.len(),
+                display_cols,
+                strip_rows,
+            );
+            let strip = compute_strip(
+                &entries,
+                starts[self.strip_page % starts.len()],
+                globals.len(),
+                heading.len(),
+                display_cols,
+                strip_rows,
+            );
+            let lines = strip_lines(&entries, globals.len(), &heading, &strip);
+            return (lines, starts.len());
+        }
+        let layout = compute_layout(
+            &entries,
+            &globals,
+            heading.len(),
+            display_cols,
+            display_rows,
+            max_height_pct,
+        );
 
         let header = Colour::Fixed(252).bold();
         let keys_style = Colour::Fixed(75).bold();
@@ -305,10 +1007,13 @@ impl State {
         let accent_switch = Colour::Fixed(180).normal();
         let dim = Colour::Fixed(244).normal();
 
-        println!(
-            "{}",
-            header.paint(format!("{:?} mode", self.mode_info.mode))
-        );
+        // The title, then any hint lines under it, dimmed and cut to fit.
+        let inner = layout.keys_col + 2 + layout.label_col;
+        for (i, line) in heading.iter().enumerate() {
+            let line = truncate_to_width(line, inner);
+            let style = if i == 0 { header } else { dim };
+            lines.push(style.paint(line).to_string());
+        }
 
         for entry in entries.iter().take(layout.visible) {
             let keys = pad_right(
@@ -316,432 +1021,2682 @@ impl State {
                 layout.keys_col,
             );
             let label = truncate_to_width(&entry.label, layout.label_col);
+            if entry.inapplicable || entry.nested {
+                lines.push(format!("{}  {}", dim.paint(keys), dim.paint(label)));
+                continue;
+            }
             let label_style = if entry.label.starts_with("Back to") {
                 accent_back
-            } else if entry.label.ends_with(" mode") {
+            } else if matches!(entry.actions.first(), Some(Action::SwitchToMode(_))) {
                 accent_switch
             } else {
                 label_style
             };
-            println!(
+            lines.push(format!(
                 "{}  {}",
                 keys_style.paint(keys),
                 label_style.paint(label)
-            );
+            ));
         }
 
         if layout.overflow > 0 {
-            println!("{}", dim.paint(format!("+{} more", layout.overflow)));
-        }
-    }
-
-    // ---- Shared sizing -------------------------------------------------------
-
-    fn entries(&self) -> Vec<Entry> {
-        let base = self.base_mode();
-        // Anything also bound in the base mode is a global (focus/resize/etc.)
-        // that works everywhere; hide those from the per-mode popup.
-        let globals: std::collections::HashSet<String> = self
-            .mode_info
-            .get_keybinds_for_mode(base)
-            .iter()
-            .map(|(key, actions)| binding_signature(key, actions, base))
-            .collect();
-        group_bindings(
-            &self.mode_info.get_mode_keybinds(),
-            self.mode_info.mode,
-            base,
-            &globals,
-        )
-    }
-
-    /// A content-sized `(x, y, width, height)` box tucked into the chosen
-    /// corner of a `cols`x`rows` display area.
-    fn corner_box_in(&self, cols: usize, rows: usize) -> (usize, usize, usize, usize) {
-        let entries = self.entries();
-        let layout = compute_layout(&entries, cols, rows, self.max_height_pct);
-        let x = match self.position {
-            Position::BottomRight => cols.saturating_sub(layout.pane_cols + MARGIN),
-            Position::BottomLeft => MARGIN,
-        };
-        let y = rows.saturating_sub(layout.pane_rows + MARGIN);
-        (x, y, layout.pane_cols, layout.pane_rows)
-    }
-
-    /// The corner box for the known display area, or `None` if it isn't known yet.
-    fn corner_box(&self) -> Option<(usize, usize, usize, usize)> {
-        if self.display_cols == 0 || self.display_rows == 0 {
-            return None;
+            lines.push(dim.paint(format!("+{} more", layout.overflow)).to_string());
         }
-        Some(self.corner_box_in(self.display_cols, self.display_rows))
-    }
-
-    /// Spawn coordinates for the controller, falling back to a guess before the
-    /// display area is known.
-    fn corner_coords(&self) -> Option<FloatingPaneCoordinates> {
-        let (x, y, w, h) = self
-            .corner_box()
-            .unwrap_or_else(|| self.corner_box_in(200, 50));
-        floating_coords(x, y, w, h)
-    }
 
-    fn update_display_area(&mut self, tabs: &[TabInfo]) {
-        if let Some(tab) = tabs.iter().find(|t| t.active) {
-            self.display_rows = tab.display_area_rows;
-            self.display_cols = tab.display_area_columns;
+        if layout.globals_shown {
+            lines.push(dim.paint("Global").to_string());
+            for entry in &globals {
+                let keys = pad_right(
+                    &truncate_to_width(&entry.keys_str(), layout.keys_col),
+                    layout.keys_col,
+                );
+                let label = truncate_to_width(&entry.label, layout.label_col);
+                lines.push(format!("{}  {}", dim.paint(keys), dim.paint(label)));
+            }
+        } else if !globals.is_empty() {
+            lines.push(dim.paint(format!("+{} global", globals.len())).to_string());
         }
+        (lines, 1)
     }
 
-    // ---- Browser -------------------------------------------------------------
+    // ---- Panel ---------------------------------------------------------------
 
-    fn update_browser(&mut self, event: Event) -> bool {
+    fn update_panel(&mut self, event: Event) -> bool {
         match event {
             Event::PermissionRequestResult(PermissionStatus::Granted) => {
-                rename_plugin_pane(self.own_id, "which-key");
-                // Grow into a large centered box for comfortable browsing.
-                let coords = FloatingPaneCoordinates::default()
-                    .with_x_percent(10)
-                    .with_y_percent(10)
-                    .with_width_percent(80)
-                    .with_height_percent(80);
-                change_floating_panes_coordinates(vec![(PaneId::Plugin(self.own_id), coords)]);
+                self.permissions_granted = true;
+                set_selectable(false);
                 false
             }
             Event::ModeUpdate(mode_info) => {
                 self.mode_info = mode_info;
+                let base = self.base_mode();
+                advance_mode_path(&mut self.mode_path, self.mode_info.mode, base);
+                if self.sort == SortOrder::Usage {
+                    // The controller keeps recording; pick up its counts.
+                    self.usage = UsageStats::load();
+                }
+                self.restart_strip_pages();
                 true
             }
-            Event::Key(key) => self.handle_browser_key(key),
-            _ => false,
-        }
-    }
-
-    fn handle_browser_key(&mut self, key: KeyWithModifier) -> bool {
-        let len = self.browser_rows().len();
-        let ctrl = key.has_modifiers(&[KeyModifier::Ctrl]);
-        match key.bare_key {
-            // The plugin API can't execute an arbitrary binding, so the browser
-            // is a read-only reference: look it up, close, press the key yourself.
-            BareKey::Esc | BareKey::Enter => {
-                close_self();
-                false
-            }
-            BareKey::Backspace => {
-                self.query.pop();
-                self.selected = 0;
+            Event::TabUpdate(tabs) => {
+                self.tabs = tabs;
                 true
             }
-            BareKey::Char('u') if ctrl => {
-                self.query.clear();
-                self.selected = 0;
-                true
-            }
-            BareKey::Char('n') if ctrl => self.move_selection(Nav::Down, len),
-            BareKey::Char('p') if ctrl => self.move_selection(Nav::Up, len),
-            BareKey::Down => self.move_selection(Nav::Down, len),
-            BareKey::Up => self.move_selection(Nav::Up, len),
-            BareKey::Char(c) if !ctrl => {
-                self.query.push(c);
-                self.selected = 0;
+            Event::PaneUpdate(manifest) => {
+                self.pane_manifest = manifest;
                 true
             }
+            Event::Timer(_) => self.turn_strip_page(),
             _ => false,
         }
     }
 
-    fn move_selection(&mut self, direction: Nav, len: usize) -> bool {
-        if len == 0 {
-            return false;
+    /// Fill our own pane, however the layout sized it, the way the popup
+    /// would lay out a box for a display just big enough to hold it.
+    fn render_panel(&mut self, rows: usize, cols: usize) {
+        let (lines, pages) = self.panel_lines(rows, cols);
+        print!("{}", lines.join("\n"));
+        self.arm_strip_timer(pages);
+    }
+
+    /// The panel's lines, cut to its height (the box and strip layouts keep
+    /// a minimum size that a short panel can't fit), and its strip pages.
+    fn panel_lines(&self, rows: usize, cols: usize) -> (Vec<String>, usize) {
+        let heading = self.popup_heading().len();
+        let (mut lines, pages) = self.hint_lines(
+            cols + BOX_FRAME_COLS,
+            rows + 2,
+            100,
+            rows.saturating_sub(heading).max(1),
+        );
+        lines.truncate(rows);
+        (lines, pages)
+    }
+
+    // ---- Shared sizing -------------------------------------------------------
+
+    /// The popup's header lines: the mode's title (or the path to it), the
+    /// tab context (with `show_context`), what to type in a text entry mode,
+    /// then the user's description for the mode, if any.
+    fn popup_heading(&self) -> Vec<String> {
+        let mode = self.mode_info.mode;
+        let mut lines = vec![breadcrumb(&self.mode_path, mode)];
+        if let Some(context) = self.tab_context().filter(|_| self.show_context) {
+            lines.push(context.summary());
         }
-        self.selected = match direction {
-            Nav::Up => self.selected.saturating_sub(1),
-            Nav::Down => (self.selected + 1).min(len - 1),
-        };
-        true
+        if let Some(prompt) = text_entry_prompt(mode) {
+            lines.push(prompt.to_string());
+        }
+        if let Some(desc) = self.mode_descriptions.get(&mode_key(mode)) {
+            lines.push(desc.clone());
+        }
+        lines
     }
 
-    /// Every binding, fuzzy-filtered by the current query. Bindings that work
-    /// in every mode (i.e. are also in the base mode) are collapsed into a
-    /// single "Global" section instead of repeating once per mode.
-    fn browser_rows(&self) -> Vec<BrowserRow> {
+    /// Signatures of every base-mode binding. Anything also bound in the
+    /// current mode is a global (focus/resize/etc.) that works everywhere.
+    fn global_signatures(&self) -> std::collections::HashSet<String> {
         let base = self.base_mode();
-        let base_binds = self.mode_info.get_keybinds_for_mode(base);
-        let globals: std::collections::HashSet<String> = base_binds
+        self.mode_info
+            .get_keybinds_for_mode(base)
             .iter()
             .map(|(key, actions)| binding_signature(key, actions, base))
-            .collect();
-        let empty = std::collections::HashSet::new();
+            .collect()
+    }
 
-        let mut rows: Vec<BrowserRow> = Vec::new();
-        let push = |rows: &mut Vec<BrowserRow>, mode: InputMode, global: bool, entry: Entry| {
-            let row = BrowserRow {
-                mode,
-                global,
-                score: 0,
-                entry,
-            };
-            let haystack = format!(
-                "{} {} {}",
-                row.mode_label(),
-                row.entry.keys_str(),
-                row.entry.label
+    /// The popup's main entries: the mode's own keys, plus globals when
+    /// `globals "show"` mixes them in. Text entry modes get confirm/cancel
+    /// guidance instead.
+    fn entries(&self) -> Vec<Entry> {
+        if self.is_base_mode() {
+            return self.mode_switch_entries();
+        }
+        let exclude = if self.globals == Globals::Show {
+            std::collections::HashSet::new()
+        } else {
+            self.global_signatures()
+        };
+        if text_entry_prompt(self.mode_info.mode).is_some() {
+            return text_entry_entries(
+                &self.mode_info.get_mode_keybinds(),
+                self.mode_info.mode,
+                self.base_mode(),
+                &exclude,
+                &self.grouping,
             );
-            if let Some(score) = fuzzy_match(&self.query, &haystack) {
-                rows.push(BrowserRow { score, ..row });
+        }
+        let mut entries = self.mode_entries(self.mode_info.mode, &exclude);
+        if let Some(previous) = previous_mode(&self.mode_path, self.mode_info.mode) {
+            for entry in &mut entries {
+                if entry.actions == [Action::SwitchToMode(previous)] {
+                    entry.label = format!("Back to {}", mode_title(previous));
+                }
             }
-        };
-
-        // Per-mode bindings with the globals filtered out.
-        for (mode, binds) in &self.mode_info.keybinds {
-            if *mode == base {
-                continue;
+        }
+        if self.sort == SortOrder::Usage {
+            sort_by_usage(
+                &mut entries,
+                self.mode_info.mode,
+                self.base_mode(),
+                &self.usage,
+            );
+        }
+        if let Some(context) = self.tab_context() {
+            for entry in &mut entries {
+                if let Some(label) = context.toggle_label(&entry.actions) {
+                    entry.label = label;
+                }
+                if self.inapplicable != Inapplicable::Show {
+                    entry.inapplicable = !context.applies(&entry.actions)
+                        && !entry.merged.iter().any(|a| context.applies(a));
+                }
+                if self.tab_names && entry.label == "Go to tab N" {
+                    entry.label = tab_list(&self.tabs);
+                }
             }
-            for entry in group_bindings(binds, *mode, base, &globals) {
-                push(&mut rows, *mode, false, entry);
+            if self.inapplicable == Inapplicable::Hide {
+                entries.retain(|e| !e.inapplicable);
             }
         }
-        // The globals, listed once.
-        for entry in group_bindings(&base_binds, base, base, &empty) {
-            push(&mut rows, base, true, entry);
-        }
-
-        let key = |r: &BrowserRow| (u8::from(r.global), mode_rank(r.mode));
-        if self.query.is_empty() {
-            rows.sort_by(|a, b| {
-                key(a)
-                    .cmp(&key(b))
-                    .then(a.entry.priority.cmp(&b.entry.priority))
-                    .then_with(|| a.entry.label.cmp(&b.entry.label))
-            });
-        } else {
-            // Best score first; on ties prefer mode-specific over global, then a
-            // more common mode (so "new pane" lands on Pane, not Tmux).
-            rows.sort_by(|a, b| {
-                b.score
-                    .cmp(&a.score)
-                    .then(key(a).cmp(&key(b)))
-                    .then_with(|| a.entry.label.cmp(&b.entry.label))
+        if self.preview_entries > 0 {
+            entries = nest_previews(entries, self.mode_info.mode, self.preview_entries, |m| {
+                self.mode_entries(m, &exclude)
             });
         }
-        rows
+        entries
     }
 
-    fn render_browser(&mut self, rows: usize, cols: usize) {
-        let title = Colour::Fixed(252).bold();
-        let prompt = Colour::Fixed(75).bold();
-        let mode_style = Colour::Fixed(108).normal();
-        let keys_style = Colour::Fixed(75).normal();
-        let label_style = Colour::Fixed(250).normal();
-        let sel_bg = Colour::Fixed(238);
-        let dim = Colour::Fixed(244).normal();
-
-        let all = self.browser_rows();
-
-        // Column widths across the visible set.
-        let mode_col = all
-            .iter()
-            .map(|r| display_width(&r.mode_label()))
-            .max()
-            .unwrap_or(0)
-            .clamp(1, 10);
-        let keys_col = all
-            .iter()
-            .map(|r| display_width(&r.entry.keys_str()))
-            .max()
-            .unwrap_or(0)
-            .clamp(1, 22);
-        let label_col = cols.saturating_sub(mode_col + keys_col + 4).max(1);
-
-        // Reserve a header row, a blank, and a footer; scroll the body so the
-        // selection stays visible.
-        let body = rows.saturating_sub(3).max(1);
-        let start = self
-            .selected
-            .saturating_sub(body - 1)
-            .min(all.len().saturating_sub(body));
-
-        let mut lines: Vec<String> = Vec::with_capacity(rows);
-        lines.push(format!(
-            "{} {}",
-            prompt.paint("Search:"),
-            title.paint(format!("{}\u{2588}", self.query))
-        ));
-        lines.push(String::new());
-
-        for (i, row) in all.iter().enumerate().skip(start).take(body) {
-            let mode = pad_right(&row.mode_label(), mode_col);
-            let keys = pad_right(
-                &truncate_to_width(&row.entry.keys_str(), keys_col),
-                keys_col,
-            );
-            let label = truncate_to_width(&row.entry.label, label_col);
-            lines.push(if i == self.selected {
-                let line = format!("{} {}  {}", mode, keys, label);
-                label_style
-                    .on(sel_bg)
-                    .paint(pad_right(&line, cols.saturating_sub(1)))
-                    .to_string()
-            } else {
-                format!(
-                    "{} {}  {}",
-                    mode_style.paint(mode),
-                    keys_style.paint(keys),
-                    label_style.paint(label)
-                )
-            });
+    /// The one-line cheat sheet shown instead of entries in the base mode
+    /// with `base_summary`; `None` otherwise.
+    fn summary(&self) -> Option<String> {
+        if !self.base_summary || !self.is_base_mode() {
+            return None;
         }
-
-        let shown = body.min(all.len().saturating_sub(start));
-        let footer = dim
-            .paint(format!(
-                "{}/{} · type to filter · ↑↓ scroll · Esc close",
-                shown,
-                all.len()
-            ))
-            .to_string();
-
-        // Pin the footer last and never emit more than `rows` lines, with no
-        // trailing newline (a final newline would scroll the header off the top).
-        lines.truncate(rows.saturating_sub(1));
-        lines.push(footer);
-        print!("{}", lines.join("\n"));
+        let base = self.base_mode();
+        Some(base_summary(
+            &self.mode_info.get_keybinds_for_mode(base),
+            base,
+        ))
     }
-}
 
-/// One row of the popup: every key bound to a single action, plus its label.
-struct Entry {
-    priority: u8,
-    keys: Vec<String>,
-    label: String,
-}
-
-impl Entry {
-    fn keys_str(&self) -> String {
-        self.keys.join(" ")
+    /// The base mode's keys into other modes: all the panel shows while
+    /// nothing else is going on.
+    fn mode_switch_entries(&self) -> Vec<Entry> {
+        let base = self.base_mode();
+        let mut entries = group_bindings(
+            &self.mode_info.get_keybinds_for_mode(base),
+            base,
+            base,
+            &std::collections::HashSet::new(),
+            &self.grouping,
+        );
+        entries
+            .retain(|e| matches!(e.actions.first(), Some(Action::SwitchToMode(m)) if *m != base));
+        entries
     }
-}
-
-/// One row of the browser: an `Entry` tagged with its mode and search score.
-/// `global` marks a binding that works in every mode (shown under "Global").
-struct BrowserRow {
-    mode: InputMode,
-    global: bool,
-    score: i32,
-    entry: Entry,
-}
 
-impl BrowserRow {
-    fn mode_label(&self) -> String {
-        if self.global {
-            "Global".to_string()
-        } else {
-            format!("{:?}", self.mode)
+    /// A mode's own bindings as popup entries, directions folded if enabled.
+    fn mode_entries(
+        &self,
+        mode: InputMode,
+        exclude: &std::collections::HashSet<String>,
+    ) -> Vec<Entry> {
+        let mut entries = group_bindings(
+            &self.mode_info.get_keybinds_for_mode(mode),
+            mode,
+            self.base_mode(),
+            exclude,
+            &self.grouping,
+        );
+        if self.compact_directions {
+            compact_directions(&mut entries);
         }
+        entries
     }
-}
-
-/// Direction the browser selection moves on an arrow / Ctrl-n / Ctrl-p key.
-/// (Named `Nav` to avoid shadowing `zellij_tile::prelude::Direction`.)
-enum Nav {
-    Up,
-    Down,
-}
 
-/// Display order for modes in the browser, base mode last.
-fn mode_rank(mode: InputMode) -> u8 {
-    match mode {
-        InputMode::Pane => 0,
-        InputMode::Tab => 1,
-        InputMode::Resize => 2,
-        InputMode::Move => 3,
-        InputMode::Scroll | InputMode::Search | InputMode::EnterSearch => 4,
-        InputMode::Session => 5,
-        InputMode::RenamePane | InputMode::RenameTab => 6,
-        InputMode::Normal | InputMode::Locked => 9,
-        _ => 7,
+    /// The focused tab and its panes, once a `TabUpdate` has arrived.
+    fn tab_context(&self) -> Option<TabContext<'_>> {
+        let tab = self.tabs.iter().find(|t| t.active)?;
+        let panes = self
+            .pane_manifest
+            .panes
+            .get(&tab.position)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Some(TabContext {
+            tab,
+            panes,
+            tab_count: self.tabs.len(),
+            own_id: self.own_id,
+        })
     }
-}
 
-/// Case-insensitive subsequence fuzzy match. Returns `None` if `query`'s chars
-/// don't all appear in order; otherwise a score that rewards contiguous runs
-/// and matches at word boundaries. An empty query matches everything (score 0).
-fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
-    if query.is_empty() {
-        return Some(0);
+    /// The globals bound in this mode, for the trailing `globals "section"`
+    /// block; empty for the other settings.
+    fn global_entries(&self) -> Vec<Entry> {
+        if self.globals != Globals::Section {
+            return Vec::new();
+        }
+        let base = self.base_mode();
+        let globals = self.global_signatures();
+        let binds: Vec<(KeyWithModifier, Vec<Action>)> = self
+            .mode_info
+            .get_mode_keybinds()
+            .into_iter()
+            .filter(|(key, actions)| globals.contains(&binding_signature(key, actions, base)))
+            .collect();
+        group_bindings(
+            &binds,
+            self.mode_info.mode,
+            base,
+            &std::collections::HashSet::new(),
+            &self.grouping,
+        )
     }
-    let mut q = query.chars().map(|c| c.to_ascii_lowercase()).peekable();
-    let mut score = 0i32;
-    let mut run = 0i32;
-    let mut prev_boundary = true;
-    let mut next = q.next();
-    for th in text.chars() {
-        let Some(qc) = next else { break };
-        let boundary = matches!(th, ' ' | '+' | '-' | '_' | '(');
-        if th.to_ascii_lowercase() == qc {
-            score += 1 + run * 2 + if prev_boundary { 3 } else { 0 };
-            run += 1;
-            next = q.next();
+
+    /// A content-sized `(x, y, width, height)` box tucked into the chosen
+    /// corner of an `(x, y, cols, rows)` area, or another corner if that one
+    /// is covered by floating panes or bars (see `place_box`).
+    fn corner_box_in(&self, area: (usize, usize, usize, usize)) -> (usize, usize, usize, usize) {
+        let (_, _, cols, rows) = area;
+        let entries = self.entries();
+        let globals = self.global_entries();
+        let (pane_cols, pane_rows) = if let Some(summary) = self.summary() {
+            let (pane_cols, pane_rows, _) =
+                summary_box(&summary, self.popup_heading().len(), cols, rows);
+            (pane_cols, pane_rows)
         } else {
-            run = 0;
-        }
-        prev_boundary = boundary;
+            match self.popup_layout {
+                PopupLayout::Box => {
+                    let layout = compute_layout(
+                        &entries,
+                        &globals,
+                        self.popup_heading().len(),
+                        cols,
+                        rows,
+                        self.max_height_pct,
+                    );
+                    (layout.pane_cols, layout.pane_rows)
+                }
+                PopupLayout::Strip => {
+                    // The first page is the fullest, so the box fits them all.
+                    let strip = compute_strip(
+                        &entries,
+                        0,
+                        globals.len(),
+                        self.popup_heading().len(),
+                        cols,
+                        self.strip_rows,
+                    );
+                    (strip.pane_cols, strip.pane_rows)
+                }
+            }
+        };
+        let context = self.tab_context();
+        let (x, y) = place_box(
+            area,
+            (pane_cols, pane_rows),
+            self.position,
+            context.as_ref().map_or(&[], |c| c.panes),
+            context.is_some_and(|c| c.tab.are_floating_panes_visible),
+            self.own_id,
+        );
+        (x, y, pane_cols, pane_rows)
     }
-    if next.is_none() && q.peek().is_none() {
-        Some(score)
-    } else {
-        None
+
+    /// The corner box for the known anchor area, or `None` if it isn't known yet.
+    fn corner_box(&self) -> Option<(usize, usize, usize, usize)> {
+        self.anchor_area().map(|area| self.corner_box_in(area))
     }
-}
 
-struct Layout {
-    pane_cols: usize,
-    pane_rows: usize,
-    keys_col: usize,
-    label_col: usize,
-    visible: usize,
-    overflow: usize,
-}
+    /// The `(x, y, cols, rows)` area to dock into: the display, or under
+    /// `anchor "focused-pane"` the focused pane (the display until one is
+    /// known). `None` before the display area is known.
+    fn anchor_area(&self) -> Option<(usize, usize, usize, usize)> {
+        if self.display_cols == 0 || self.display_rows == 0 {
+            return None;
+        }
+        let display = (0, 0, self.display_cols, self.display_rows);
+        if self.anchor == Anchor::Display {
+            return Some(display);
+        }
+        let pane = self.tab_context().and_then(|c| c.focused_pane());
+        Some(pane.map_or(display, |p| {
+            (p.pane_x, p.pane_y, p.pane_columns, p.pane_rows)
+        }))
+    }
 
-/// Group a mode's keybindings by action, ordered by priority, dropping noise.
-/// Group a mode's keybindings by action, dropping noise and any binding whose
-/// signature is in `exclude` (used to hide globals from the per-mode popup).
-fn group_bindings(
-    binds: &[(KeyWithModifier, Vec<Action>)],
-    mode: InputMode,
-    base_mode: InputMode,
-    exclude: &std::collections::HashSet<String>,
-) -> Vec<Entry> {
-    let mut order: Vec<String> = Vec::new();
-    let mut by_label: HashMap<String, Entry> = HashMap::new();
+    /// Spawn coordinates for the controller, falling back to a guess before the
+    /// display area is known.
+    fn corner_coords(&self) -> Option<FloatingPaneCoordinates> {
+        let (x, y, w, h) = self
+            .corner_box()
+            .unwrap_or_else(|| self.corner_box_in((0, 0, 200, 50)));
+        floating_coords(x, y, w, h)
+    }
 
-    for (key, actions) in binds {
-        if is_noise(actions) || exclude.contains(&binding_signature(key, actions, base_mode)) {
-            continue;
-        }
-        let label = format_action(actions, base_mode);
-        let entry = by_label.entry(label.clone()).or_insert_with(|| {
-            order.push(label.clone());
-            Entry {
-                priority: action_priority(actions, mode, base_mode),
-                keys: Vec::new(),
-                label,
-            }
-        });
-        let key_str = format_key(key);
-        if !entry.keys.contains(&key_str) {
-            entry.keys.push(key_str);
+    fn update_display_area(&mut self, tabs: &[TabInfo]) {
+        if let Some(tab) = tabs.iter().find(|t| t.active) {
+            self.display_rows = tab.display_area_rows;
+            self.display_cols = tab.display_area_columns;
         }
     }
 
-    let mut entries: Vec<Entry> = order
-        .into_iter()
-        .filter_map(|label| by_label.remove(&label))
-        .collect();
-    entries.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.label.cmp(&b.label)));
-    entries
-}
+    // ---- Browser -------------------------------------------------------------
 
-fn floating_coords(x: usize, y: usize, w: usize, h: usize) -> Option<FloatingPaneCoordinates> {
+    fn update_browser(&mut self, event: Event) -> bool {
+        match event {
+            Event::PermissionRequestResult(PermissionStatus::Granted) => {
+                rename_plugin_pane(self.own_id, "which-key");
+                // Grow into a large centered box for comfortable browsing.
+                let coords = FloatingPaneCoordinates::default()
+                    .with_x_percent(10)
+                    .with_y_percent(10)
+                    .with_width_percent(80)
+                    .with_height_percent(80);
+                change_floating_panes_coordinates(vec![(PaneId::Plugin(self.own_id), coords)]);
+                false
+            }
+            Event::ModeUpdate(mode_info) => {
+                self.mode_info = mode_info;
+                true
+            }
+            Event::Key(key) => {
+                let changed = self.handle_browser_key(key);
+                if self.closing {
+                    self.history.record(self.query.text());
+                    self.history.save();
+                    close_self();
+                }
+                changed
+            }
+            Event::Mouse(mouse) => self.handle_browser_mouse(mouse),
+            _ => false,
+        }
+    }
+
+    fn handle_browser_key(&mut self, key: KeyWithModifier) -> bool {
+        if self.browser_keys == BrowserKeys::Vim {
+            if self.vim_normal {
+                return self.handle_vim_normal_key(key);
+            }
+            if key.bare_key == BareKey::Esc && key.has_no_modifiers() {
+                self.vim_normal = true;
+                return true;
+            }
+        }
+        let len = self.browser_rows().len();
+        let ctrl = key.has_modifiers(&[KeyModifier::Ctrl]);
+        let alt = key.has_modifiers(&[KeyModifier::Alt]);
+        let page = self.browser_page.max(1);
+        let half = (page / 2).max(1);
+        match key.bare_key {
+            // The plugin API can't execute an arbitrary binding, so the browser
+            // is a read-only reference: look it up, close, press the key yourself.
+            BareKey::Esc | BareKey::Enter => {
+                self.closing = true;
+                false
+            }
+            BareKey::Tab => {
+                self.show_detail = !self.show_detail;
+                true
+            }
+            BareKey::Char('o') if ctrl => {
+                self.grouping.show_noise = !self.grouping.show_noise;
+                self.selected = 0;
+                true
+            }
+            BareKey::Char('t') if ctrl => {
+                self.browser_view = self.browser_view.next();
+                self.selected = 0;
+                true
+            }
+            BareKey::Char('n') if ctrl => self.move_selection(Nav::Down(1), len),
+            BareKey::Char('p') if ctrl => self.move_selection(Nav::Up(1), len),
+            BareKey::Char('d') if ctrl => self.move_selection(Nav::Down(half), len),
+            BareKey::Char('u') if ctrl => self.move_selection(Nav::Up(half), len),
+            // Zellij's default keymap takes Alt+p / Alt+n before we see them;
+            // Ctrl+↑ / Ctrl+↓ are free.
+            BareKey::Up if ctrl => self.recall_older(),
+            BareKey::Down if ctrl => self.recall_newer(),
+            BareKey::Char('p') if alt => self.recall_older(),
+            BareKey::Char('n') if alt => self.recall_newer(),
+            BareKey::Down => self.move_selection(Nav::Down(1), len),
+            BareKey::Up => self.move_selection(Nav::Up(1), len),
+            BareKey::PageDown => self.move_selection(Nav::Down(page), len),
+            BareKey::PageUp => self.move_selection(Nav::Up(page), len),
+            BareKey::Home => self.move_selection(Nav::Top, len),
+            BareKey::End => self.move_selection(Nav::Bottom, len),
+            _ => self.edit_query(&key),
+        }
+    }
+
+    /// Vim normal mode: j/k/gg/G and Ctrl+d/Ctrl+u move (as do the arrow and
+    /// paging keys), `/` goes back to the query, and Esc (or Enter) closes.
+    fn handle_vim_normal_key(&mut self, key: KeyWithModifier) -> bool {
+        let len = self.browser_rows().len();
+        let ctrl = key.has_modifiers(&[KeyModifier::Ctrl]);
+        let page = self.browser_page.max(1);
+        let half = (page / 2).max(1);
+        let pending_g = std::mem::take(&mut self.pending_g);
+        match key.bare_key {
+            BareKey::Esc | BareKey::Enter => {
+                self.closing = true;
+                false
+            }
+            BareKey::Tab => {
+                self.show_detail = !self.show_detail;
+                true
+            }
+            BareKey::Char('o') if ctrl => {
+                self.grouping.show_noise = !self.grouping.show_noise;
+                self.selected = 0;
+                true
+            }
+            BareKey::Char('t') if ctrl => {
+                self.browser_view = self.browser_view.next();
+                self.selected = 0;
+                true
+            }
+            BareKey::Char('n') if ctrl => self.move_selection(Nav::Down(1), len),
+            BareKey::Char('p') if ctrl => self.move_selection(Nav::Up(1), len),
+            BareKey::Char('d') if ctrl => self.move_selection(Nav::Down(half), len),
+            BareKey::Char('u') if ctrl => self.move_selection(Nav::Up(half), len),
+            BareKey::Char('j') | BareKey::Down if !ctrl => self.move_selection(Nav::Down(1), len),
+            BareKey::Char('k') | BareKey::Up if !ctrl => self.move_selection(Nav::Up(1), len),
+            BareKey::PageDown => self.move_selection(Nav::Down(page), len),
+            BareKey::PageUp => self.move_selection(Nav::Up(page), len),
+            BareKey::Home => self.move_selection(Nav::Top, len),
+            BareKey::End => self.move_selection(Nav::Bottom, len),
+            BareKey::Char('G') => self.move_selection(Nav::Bottom, len),
+            BareKey::Char('g') if pending_g => self.move_selection(Nav::Top, len),
+            BareKey::Char('g') => {
+                self.pending_g = true;
+                false
+            }
+            BareKey::Char('/') => {
+                self.vim_normal = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Apply a line-editing key to the search prompt. Returns whether anything
+    /// changed; a changed query resets the selection and history browsing.
+    fn edit_query(&mut self, key: &KeyWithModifier) -> bool {
+        let ctrl = key.has_modifiers(&[KeyModifier::Ctrl]);
+        let alt = key.has_modifiers(&[KeyModifier::Alt]);
+        let before = self.query.text().to_string();
+        let changed = match key.bare_key {
+            // Ctrl+← / Ctrl+→ for when zellij keeps Alt+f (floating panes).
+            BareKey::Left if ctrl => self.query.word_left(),
+            BareKey::Right if ctrl => self.query.word_right(),
+            BareKey::Left => self.query.left(),
+            BareKey::Right => self.query.right(),
+            BareKey::Char('a') if ctrl => self.query.home(),
+            BareKey::Char('e') if ctrl => self.query.end(),
+            BareKey::Char('b') if alt => self.query.word_left(),
+            BareKey::Char('f') if alt => self.query.word_right(),
+            BareKey::Char('w') if ctrl => self.query.delete_word_back(),
+            BareKey::Backspace => self.query.backspace(),
+            BareKey::Delete => self.query.delete(),
+            BareKey::Char(c) if !ctrl && !alt => {
+                self.query.insert(c);
+                true
+            }
+            _ => false,
+        };
+        if self.query.text() != before {
+            self.selected = 0;
+            self.history.reset();
+        }
+        changed
+    }
+
+    fn recall_older(&mut self) -> bool {
+        let recalled = self.history.older(self.query.text());
+        self.recall_query(recalled)
+    }
+
+    fn recall_newer(&mut self) -> bool {
+        let recalled = self.history.newer();
+        self.recall_query(recalled)
+    }
+
+    fn recall_query(&mut self, recalled: Option<String>) -> bool {
+        let Some(text) = recalled else {
+            return false;
+        };
+        self.query = LineEditor::new(text);
+        self.selected = 0;
+        true
+    }
+
+    fn move_selection(&mut self, direction: Nav, len: usize) -> bool {
+        if len == 0 {
+            return false;
+        }
+        self.selected = nav_target(self.selected, direction, len);
+        true
+    }
+
+    /// Wheel scrolls the selection; a left click on a list row selects it.
+    fn handle_browser_mouse(&mut self, mouse: Mouse) -> bool {
+        let len = self.browser_rows().len();
+        match mouse {
+            Mouse::ScrollUp(n) => self.move_selection(Nav::Up(n.max(1)), len),
+            Mouse::ScrollDown(n) => self.move_selection(Nav::Down(n.max(1)), len),
+            Mouse::LeftClick(line, col) => {
+                // Body rows start below the search prompt and the blank line.
+                let Some(offset) = usize::try_from(line)
+                    .ok()
+                    .and_then(|l| l.checked_sub(BROWSER_BODY_TOP))
+                else {
+                    return false;
+                };
+                let index = self.browser_scroll + offset;
+                if offset >= self.browser_page || col >= self.browser_list_cols || index >= len {
+                    return false;
+                }
+                self.selected = index;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The rows for the current view, fuzzy-filtered by the current query.
+    fn browser_rows(&self) -> Vec<BrowserRow> {
+        let candidates = match self.browser_view {
+            BrowserView::All => self.all_rows(),
+            BrowserView::MostUsed => self.most_used_rows(),
+            BrowserView::Unused => self.unused_rows(),
+        };
+        let mut rows: Vec<BrowserRow> = candidates
+            .into_iter()
+            .filter_map(|row| {
+                let haystack = format!(
+                    "{} {} {}",
+                    row.mode_label(),
+                    row.entry.keys_str(),
+                    row.entry.label
+                );
+                let score = fuzzy_match(self.query.text(), &haystack)?;
+                Some(BrowserRow { score, ..row })
+            })
+            .collect();
+
+        let key = |r: &BrowserRow| (u8::from(r.global), mode_rank(r.mode));
+        // Within a view: most used first, or least recently used (never used
+        // at the very top), or the usual mode / priority order.
+        let view_order = |a: &BrowserRow, b: &BrowserRow| match self.browser_view {
+            BrowserView::All => key(a)
+                .cmp(&key(b))
+                .then(a.entry.order_key().cmp(&b.entry.order_key())),
+            BrowserView::MostUsed => b.count().cmp(&a.count()).then(key(a).cmp(&key(b))),
+            BrowserView::Unused => a.last_used().cmp(&b.last_used()).then(key(a).cmp(&key(b))),
+        };
+        if self.query.text().is_empty() {
+            rows.sort_by(|a, b| view_order(a, b).then_with(|| a.entry.label.cmp(&b.entry.label)));
+        } else {
+            // Best score first; on ties prefer mode-specific over global, then a
+            // more common mode (so "new pane" lands on Pane, not Tmux).
+            rows.sort_by(|a, b| {
+                b.score
+                    .cmp(&a.score)
+                    .then(view_order(a, b))
+                    .then_with(|| a.entry.label.cmp(&b.entry.label))
+            });
+        }
+        rows
+    }
+
+    /// Every binding. Bindings that work in every mode (i.e. are also in the
+    /// base mode) are collapsed into a single "Global" section instead of
+    /// repeating once per mode.
+    fn all_rows(&self) -> Vec<BrowserRow> {
+        let base = self.base_mode();
+        let base_binds = self.mode_info.get_keybinds_for_mode(base);
+        let globals = self.global_signatures();
+        let empty = std::collections::HashSet::new();
+
+        let mut rows: Vec<BrowserRow> = Vec::new();
+        // Per-mode bindings with the globals filtered out.
+        for (mode, binds) in &self.mode_info.keybinds {
+            if *mode == base {
+                continue;
+            }
+            for entry in group_bindings(binds, *mode, base, &globals, &self.grouping) {
+                rows.push(BrowserRow::new(*mode, false, entry));
+            }
+        }
+        // The globals, listed once.
+        for entry in group_bindings(&base_binds, base, base, &empty, &self.grouping) {
+            rows.push(BrowserRow::new(base, true, entry));
+        }
+        rows
+    }
+
+    /// One row per mode switch the controller has recorded, attributed to the
+    /// bindings that could have caused it.
+    fn most_used_rows(&self) -> Vec<BrowserRow> {
+        let mut rows = Vec::new();
+        for ((from, to), stats) in &self.usage.transitions {
+            let (Some(from), Some(to)) = (self.mode_named(from), self.mode_named(to)) else {
+                continue;
+            };
+            if let Some(entry) = self.transition_entry(from, to) {
+                rows.push(BrowserRow {
+                    stats: Some(*stats),
+                    ..BrowserRow::new(from, false, entry)
+                });
+            }
+        }
+        rows
+    }
+
+    /// Every mode switch that's bound to a key but hasn't been seen for
+    /// `UNUSED_AFTER_SECS` (or ever).
+    fn unused_rows(&self) -> Vec<BrowserRow> {
+        let cutoff = now_secs().saturating_sub(UNUSED_AFTER_SECS);
+        let mut rows = Vec::new();
+        for (from, binds) in &self.mode_info.keybinds {
+            let mut targets: Vec<InputMode> = binds
+                .iter()
+                .filter_map(|(_, actions)| switch_target(actions))
+                .filter(|to| to != from)
+                .collect();
+            targets.sort();
+            targets.dedup();
+            for to in targets {
+                let stats = self.usage.get(*from, to);
+                if stats.is_some_and(|s| s.last_used >= cutoff) {
+                    continue;
+                }
+                if let Some(entry) = self.transition_entry(*from, to) {
+                    rows.push(BrowserRow {
+                        stats,
+                        ..BrowserRow::new(*from, false, entry)
+                    });
+                }
+            }
+        }
+        rows
+    }
+
+    /// The bindings in `from` that switch to `to`, merged into one entry. A
+    /// mode switch can't tell which of several such keys was pressed, so they
+    /// share the row, labelled as the switch it counts ("Exit to Normal mode
+    /// via New pane / Close pane / ...").
+    fn transition_entry(&self, from: InputMode, to: InputMode) -> Option<Entry> {
+        let base = self.base_mode();
+        let binds: Vec<(KeyWithModifier, Vec<Action>)> = self
+            .mode_info
+            .get_keybinds_for_mode(from)
+            .into_iter()
+            .filter(|(_, actions)| switch_target(actions) == Some(to))
+            .collect();
+        let empty = std::collections::HashSet::new();
+        let grouping = Grouping {
+            show_noise: true,
+            ..self.grouping.clone()
+        };
+        let mut entries = group_bindings(&binds, from, base, &empty, &grouping).into_iter();
+        let mut merged = entries.next()?;
+        let mut labels = vec![merged.label.clone()];
+        for entry in entries {
+            merged.keys.extend(entry.keys);
+            labels.push(entry.label);
+        }
+        if labels.len() > 1 {
+            merged.label = format!("Exit to {} via {}", mode_heading(to), labels.join(" / "));
+        }
+        Some(merged)
+    }
+
+    /// The mode whose `Debug` name is `name`, among the modes with bindings.
+    fn mode_named(&self, name: &str) -> Option<InputMode> {
+        self.mode_info
+            .keybinds
+            .iter()
+            .map(|(mode, _)| *mode)
+            .find(|mode| format!("{:?}", mode) == name)
+    }
+
+    fn render_browser(&mut self, rows: usize, cols: usize) {
+        let title = Colour::Fixed(252).bold();
+        let prompt = Colour::Fixed(75).bold();
+        let mode_style = Colour::Fixed(108).normal();
+        let keys_style = Colour::Fixed(75).normal();
+        let label_style = Colour::Fixed(250).normal();
+        let sel_bg = Colour::Fixed(238);
+        let dim = Colour::Fixed(244).normal();
+
+        let all = self.browser_rows();
+
+        // The detail pane sits to the right on wide screens and below the list
+        // otherwise; either way it describes the selected row.
+        let side = self.show_detail && cols >= DETAIL_SIDE_MIN_COLS;
+        let detail_cols = if side {
+            (cols * 2 / 5).clamp(30, 60)
+        } else {
+            cols
+        };
+        let list_cols = if side {
+            cols.saturating_sub(detail_cols + 3)
+        } else {
+            cols
+        };
+        let below = if self.show_detail && !side {
+            (rows / 3).max(4)
+        } else {
+            0
+        };
+
+        // Column widths across the visible set.
+        let mode_col = all
+            .iter()
+            .map(|r| display_width(&r.mode_label()))
+            .max()
+            .unwrap_or(0)
+            .clamp(1, 12);
+        let keys_col = all
+            .iter()
+            .map(|r| display_width(&r.entry.keys_str()))
+            .max()
+            .unwrap_or(0)
+            .clamp(1, 22);
+        // The usage views add a count / last-used column after the label.
+        let now = now_secs();
+        let note = |row: &BrowserRow| match self.browser_view {
+            BrowserView::All => String::new(),
+            BrowserView::MostUsed => format!("×{}", row.count()),
+            BrowserView::Unused => row
+                .last_used()
+                .map_or("never".to_string(), |t| format_age(now.saturating_sub(t))),
+        };
+        let note_col = all
+            .iter()
+            .map(|r| display_width(&note(r)))
+            .max()
+            .unwrap_or(0);
+        let note_gap = if note_col > 0 { note_col + 2 } else { 0 };
+        let label_col = list_cols
+            .saturating_sub(mode_col + keys_col + 4 + note_gap)
+            .max(1);
+
+        // Reserve a header row, a blank, and a footer (plus the detail block
+        // when it's below); scroll the body so the selection stays visible.
+        let body = rows.saturating_sub(3 + below).max(1);
+        let start = self
+            .selected
+            .saturating_sub(body - 1)
+            .min(all.len().saturating_sub(body));
+        self.browser_scroll = start;
+        self.browser_page = body;
+        self.browser_list_cols = list_cols;
+
+        let mut lines: Vec<String> = Vec::with_capacity(rows);
+        let (before, at, after) = self.query.split_at_cursor();
+        // No cursor while vim normal mode owns the keys.
+        let cursor = if self.vim_normal {
+            title
+        } else {
+            title.reverse()
+        };
+        let view = match self.browser_view {
+            BrowserView::All => String::new(),
+            BrowserView::MostUsed => "  [Most used]".to_string(),
+            BrowserView::Unused => format!("  [Unused for {} days]", UNUSED_AFTER_SECS / DAY_SECS),
+        };
+        lines.push(format!(
+            "{} {}{}{}{}",
+            prompt.paint("Search:"),
+            title.paint(before),
+            cursor.paint(at),
+            title.paint(after),
+            mode_style.paint(view)
+        ));
+        lines.push(String::new());
+
+        let detail = if self.show_detail {
+            all.get(self.selected)
+                .map(|row| self.browser_detail(row, detail_cols.saturating_sub(1)))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        let paint_detail = |line: &DetailLine| match line {
+            DetailLine::Heading(text) => title.paint(text.as_str()).to_string(),
+            DetailLine::Text(text) => label_style.paint(text.as_str()).to_string(),
+        };
+
+        // Beside the list, the detail pane can run past the last list row, so
+        // the body is as tall as whichever is longer.
+        let visible: Vec<(usize, &BrowserRow)> =
+            all.iter().enumerate().skip(start).take(body).collect();
+        let body_rows = if side {
+            visible.len().max(detail.len()).min(body)
+        } else {
+            visible.len()
+        };
+        for n in 0..body_rows {
+            let mut line = match visible.get(n) {
+                // Past the last list row, beside a longer detail pane.
+                None => " ".repeat(list_cols.saturating_sub(1)),
+                Some(&(i, row)) => {
+                    let mode = pad_right(&row.mode_label(), mode_col);
+                    let keys = pad_right(
+                        &truncate_to_width(&row.entry.keys_str(), keys_col),
+                        keys_col,
+                    );
+                    let mut label = truncate_to_width(&row.entry.label, label_col);
+                    if note_col > 0 {
+                        label = format!("{}  {}", pad_right(&label, label_col), note(row));
+                    }
+                    if i == self.selected {
+                        // As wide as the other rows, so the divider lines up.
+                        let line = format!("{} {}  {}", mode, keys, label);
+                        label_style
+                            .on(sel_bg)
+                            .paint(pad_right(&line, list_cols.saturating_sub(1)))
+                            .to_string()
+                    } else {
+                        // Beside the detail pane the label is padded so the divider lines up.
+                        let label = if side {
+                            pad_right(&label, label_col + note_gap)
+                        } else {
+                            label
+                        };
+                        // Bindings hidden from the popup are listed, but dimmed.
+                        let (mode_style, keys_style, label_style) = if row.entry.hidden {
+                            (dim, dim, dim)
+                        } else {
+                            (mode_style, keys_style, label_style)
+                        };
+                        format!(
+                            "{} {}  {}",
+                            mode_style.paint(mode),
+                            keys_style.paint(keys),
+                            label_style.paint(label)
+                        )
+                    }
+                }
+            };
+            if side {
+                line.push_str(&format!(" {} ", dim.paint("│")));
+                if let Some(d) = detail.get(n) {
+                    line.push_str(&paint_detail(d));
+                }
+            }
+            lines.push(line);
+        }
+
+        if below > 0 {
+            // Keep the detail block anchored just above the footer.
+            lines.resize(BROWSER_BODY_TOP + body, String::new());
+            lines.push(dim.paint("─".repeat(cols.saturating_sub(1))).to_string());
+            lines.extend(detail.iter().take(below - 1).map(paint_detail));
+        }
+
+        let shown = body.min(all.len().saturating_sub(start));
+        let hints = match (self.browser_keys, self.vim_normal) {
+            (BrowserKeys::Default, _) => {
+                "type to filter · ↑↓ PgUp/PgDn scroll · Tab details · ^o raw input · ^t view · Esc close"
+            }
+            (BrowserKeys::Vim, false) => {
+                "-- INSERT -- type to filter · ↑↓ scroll · Esc normal mode"
+            }
+            (BrowserKeys::Vim, true) => {
+                "-- NORMAL -- j/k gg/G ^d/^u PgUp/PgDn move · / search · Tab details · ^o raw input · ^t view · Esc close"
+            }
+        };
+        let footer = dim
+            .paint(format!("{}/{} · {}", shown, all.len(), hints))
+            .to_string();
+
+        // Pin the footer last and never emit more than `rows` lines, with no
+        // trailing newline (a final newline would scroll the header off the top).
+        lines.truncate(rows.saturating_sub(1));
+        lines.push(footer);
+        print!("{}", lines.join("\n"));
+    }
+
+    /// The detail pane for a browser row: the full action chain, its category,
+    /// and every mode (with keys) where the same chain is bound.
+    fn browser_detail(&self, row: &BrowserRow, width: usize) -> Vec<DetailLine> {
+        let base = self.base_mode();
+        let entry = &row.entry;
+        let mut out = vec![DetailLine::Heading(truncate_to_width(
+            &format!("{}  {}", entry.keys_str(), entry.label),
+            width,
+        ))];
+        let text = |out: &mut Vec<DetailLine>, s: String| {
+            out.extend(wrap_to_width(&s, width).into_iter().map(DetailLine::Text));
+        };
+
+        text(
+            &mut out,
+            format!("Category: {}", action_category(&entry.actions, base)),
+        );
+        text(
+            &mut out,
+            format!("Global: {}", if row.global { "yes" } else { "no" }),
+        );
+        if entry.hidden {
+            text(&mut out, "Hidden from the popup by config".to_string());
+        }
+
+        out.push(DetailLine::Heading("Actions".to_string()));
+        for (i, action) in entry.actions.iter().enumerate() {
+            let line = match action {
+                // A trailing mode switch is the binding's side effect.
+                Action::SwitchToMode(mode) if i > 0 => {
+                    format!("{}. then switch to {}", i + 1, mode_heading(*mode))
+                }
+                other => format!("{}. {:?}", i + 1, other),
+            };
+            text(&mut out, line);
+        }
+
+        out.push(DetailLine::Heading("Bound in".to_string()));
+        for (mode, keys) in self.modes_binding(&entry.actions) {
+            let name = if mode == base {
+                format!("{} (base)", mode_title(mode))
+            } else {
+                mode_title(mode).to_string()
+            };
+            text(&mut out, format!("{}: {}", name, keys.join(" ")));
+        }
+        out
+    }
+
+    /// Every mode that binds exactly `actions`, with the keys it uses there,
+    /// in browser mode order.
+    fn modes_binding(&self, actions: &[Action]) -> Vec<(InputMode, Vec<String>)> {
+        let mut found: Vec<(InputMode, Vec<String>)> = self
+            .mode_info
+            .keybinds
+            .iter()
+            .filter_map(|(mode, binds)| {
+                let keys: Vec<String> = binds
+                    .iter()
+                    .filter(|(_, a)| a.as_slice() == actions)
+                    .map(|(key, _)| format_key(key))
+                    .collect();
+                (!keys.is_empty()).then_some((*mode, keys))
+            })
+            .collect();
+        found.sort_by_key(|(mode, _)| mode_rank(*mode));
+        found
+    }
+}
+
+/// One line of the browser's detail pane.
+enum DetailLine {
+    Heading(String),
+    Text(String),
+}
+
+/// A single-line text editor for the browser's search prompt. The cursor is
+/// a char index into `text`. Movement and deletion methods return whether
+/// they did anything.
+#[derive(Default)]
+struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// An editor holding `text`, cursor at the end.
+    fn new(text: String) -> Self {
+        let cursor = text.chars().count();
+        Self { text, cursor }
+    }
+
+    fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    /// The text before the cursor, the char under it (a space at the end),
+    /// and the rest.
+    fn split_at_cursor(&self) -> (String, String, String) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let before = chars[..self.cursor].iter().collect();
+        let at = chars
+            .get(self.cursor)
+            .map_or(" ".to_string(), char::to_string);
+        let after = chars.iter().skip(self.cursor + 1).collect();
+        (before, at, after)
+    }
+
+    fn insert(&mut self, c: char) {
+        let i = self.byte_index(self.cursor);
+        self.text.insert(i, c);
+        self.cursor += 1;
+    }
+
+    fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        let i = self.byte_index(self.cursor);
+        self.text.remove(i);
+        true
+    }
+
+    fn delete(&mut self) -> bool {
+        if self.cursor >= self.len() {
+            return false;
+        }
+        let i = self.byte_index(self.cursor);
+        self.text.remove(i);
+        true
+    }
+
+    fn move_to(&mut self, cursor: usize) -> bool {
+        let moved = cursor != self.cursor;
+        self.cursor = cursor;
+        moved
+    }
+
+    fn left(&mut self) -> bool {
+        self.move_to(self.cursor.saturating_sub(1))
+    }
+
+    fn right(&mut self) -> bool {
+        self.move_to((self.cursor + 1).min(self.len()))
+    }
+
+    fn home(&mut self) -> bool {
+        self.move_to(0)
+    }
+
+    fn end(&mut self) -> bool {
+        self.move_to(self.len())
+    }
+
+    /// Start of the word before the cursor (skipping any spaces first).
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.cursor;
+        while i > 0 && chars[i - 1] == ' ' {
+            i -= 1;
+        }
+        while i > 0 && chars[i - 1] != ' ' {
+            i -= 1;
+        }
+        i
+    }
+
+    fn word_left(&mut self) -> bool {
+        self.move_to(self.word_start())
+    }
+
+    /// Move past the end of the next word.
+    fn word_right(&mut self) -> bool {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut i = self.cursor;
+        while i < chars.len() && chars[i] == ' ' {
+            i += 1;
+        }
+        while i < chars.len() && chars[i] != ' ' {
+            i += 1;
+        }
+        self.move_to(i)
+    }
+
+    fn delete_word_back(&mut self) -> bool {
+        let start = self.word_start();
+        if start == self.cursor {
+            return false;
+        }
+        let (from, to) = (self.byte_index(start), self.byte_index(self.cursor));
+        self.text.replace_range(from..to, "");
+        self.cursor = start;
+        true
+    }
+}
+
+/// The browser's query history, newest last, recalled shell-style. `pos` is
+/// the entry being shown while browsing; `draft` is the query typed before
+/// browsing began, restored when you step past the newest entry.
+#[derive(Default)]
+struct QueryHistory {
+    entries: Vec<String>,
+    pos: Option<usize>,
+    draft: String,
+}
+
+impl QueryHistory {
+    fn load() -> Self {
+        let entries = std::fs::read_to_string(HISTORY_PATH)
+            .map(|s| parse_history(&s))
+            .unwrap_or_default();
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Best effort: a read-only or missing `/data` just means no history.
+    fn save(&self) {
+        let _ = std::fs::write(HISTORY_PATH, self.entries.join("\n"));
+    }
+
+    /// Remember `query`, moving a repeat to the newest slot.
+    fn record(&mut self, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.entries.retain(|q| q != query);
+        self.entries.push(query.to_string());
+        let excess = self.entries.len().saturating_sub(HISTORY_MAX);
+        self.entries.drain(..excess);
+        self.reset();
+    }
+
+    /// Stop browsing (the query was edited).
+    fn reset(&mut self) {
+        self.pos = None;
+    }
+
+    /// Step to an older entry, stashing `current` as the draft on the first step.
+    fn older(&mut self, current: &str) -> Option<String> {
+        let pos = match self.pos {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(p) => p.checked_sub(1)?,
+        };
+        self.pos = Some(pos);
+        Some(self.entries[pos].clone())
+    }
+
+    /// Step to a newer entry, or back to the draft past the newest.
+    fn newer(&mut self) -> Option<String> {
+        let pos = self.pos?;
+        if pos + 1 < self.entries.len() {
+            self.pos = Some(pos + 1);
+            Some(self.entries[pos + 1].clone())
+        } else {
+            self.pos = None;
+            Some(std::mem::take(&mut self.draft))
+        }
+    }
+}
+
+/// How often, and when last, one mode switch or action was seen.
+#[derive(Default, Clone, Copy, PartialEq, Debug)]
+struct TransitionStats {
+    count: u64,
+    last_used: u64,
+}
+
+impl TransitionStats {
+    fn bump(&mut self, now: u64) {
+        self.count += 1;
+        self.last_used = now;
+    }
+}
+
+/// Mode switches seen by the controller, keyed by `(from, to)` mode names,
+/// and actions inferred from tab and pane changes, keyed by `(mode, action
+/// variant)`. Zellij doesn't report keypresses, but a switch from one mode to
+/// another pins down which bindings could have caused it, and a new pane
+/// while in Pane mode which action did. `since` is when tracking started.
+/// Persisted in `/data` as a versioned text file.
+#[derive(Default, Debug, PartialEq)]
+struct UsageStats {
+    since: u64,
+    transitions: BTreeMap<(String, String), TransitionStats>,
+    actions: BTreeMap<(String, String), TransitionStats>,
+}
+
+impl UsageStats {
+    fn load() -> Self {
+        std::fs::read_to_string(USAGE_PATH)
+            .ok()
+            .and_then(|s| parse_usage(&s))
+            .unwrap_or_default()
+    }
+
+    fn get(&self, from: InputMode, to: InputMode) -> Option<TransitionStats> {
+        self.transitions
+            .get(&(format!("{:?}", from), format!("{:?}", to)))
+            .copied()
+    }
+
+    fn get_action(&self, mode: InputMode, variant: &str) -> Option<TransitionStats> {
+        self.actions
+            .get(&(format!("{:?}", mode), variant.to_string()))
+            .copied()
+    }
+
+    /// Count a switch, merging with the file like `Learning::record` does.
+    fn record(&mut self, from: InputMode, to: InputMode, now: u64) {
+        self.update(now, |stats| {
+            stats
+                .transitions
+                .entry((format!("{:?}", from), format!("{:?}", to)))
+                .or_default()
+                .bump(now);
+        });
+    }
+
+    /// Count each of the action variants that could explain a change seen
+    /// in `mode`.
+    fn record_actions(&mut self, mode: InputMode, variants: &[&str], now: u64) {
+        self.update(now, |stats| {
+            for variant in variants {
+                stats
+                    .actions
+                    .entry((format!("{:?}", mode), variant.to_string()))
+                    .or_default()
+                    .bump(now);
+            }
+        });
+    }
+
+    fn update(&mut self, now: u64, f: impl FnOnce(&mut Self)) {
+        if let Some(on_disk) = std::fs::read_to_string(USAGE_PATH)
+            .ok()
+            .and_then(|s| parse_usage(&s))
+        {
+            *self = on_disk;
+        }
+        if self.since == 0 {
+            self.since = now;
+        }
+        f(self);
+        let _ = std::fs::write(USAGE_PATH, format_usage(self));
+    }
+}
+
+/// Parse the usage file; `None` for a missing or unknown version header, so
+/// an incompatible file is started over rather than misread.
+fn parse_usage(s: &str) -> Option<UsageStats> {
+    let mut lines = s.lines();
+    if lines.next()? != USAGE_HEADER {
+        return None;
+    }
+    let mut stats = UsageStats::default();
+    for line in lines {
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields.as_slice() {
+            ["since", since] => stats.since = since.parse().ok()?,
+            ["action", mode, variant, count, last_used] => {
+                let (Ok(count), Ok(last_used)) = (count.parse(), last_used.parse()) else {
+                    continue;
+                };
+                stats.actions.insert(
+                    (mode.to_string(), variant.to_string()),
+                    TransitionStats { count, last_used },
+                );
+            }
+            [from, to, count, last_used] => {
+                let (Ok(count), Ok(last_used)) = (count.parse(), last_used.parse()) else {
+                    continue;
+                };
+                stats.transitions.insert(
+                    (from.to_string(), to.to_string()),
+                    TransitionStats { count, last_used },
+                );
+            }
+            _ => {}
+        }
+    }
+    Some(stats)
+}
+
+fn format_usage(stats: &UsageStats) -> String {
+    let mut out = format!("{}\nsince\t{}\n", USAGE_HEADER, stats.since);
+    for ((from, to), t) in &stats.transitions {
+        out.push_str(&format!("{}\t{}\t{}\t{}\n", from, to, t.count, t.last_used));
+    }
+    for ((mode, variant), t) in &stats.actions {
+        out.push_str(&format!(
+            "action\t{}\t{}\t{}\t{}\n",
+            mode, variant, t.count, t.last_used
+        ));
+    }
+    out
+}
+
+/// Just the counts, compact enough for a plugin config value:
+/// `from>to=count` for switches and `mode:Action=count` for actions, joined
+/// with commas.
+fn format_usage_counts(stats: &UsageStats) -> String {
+    let switches = stats
+        .transitions
+        .iter()
+        .map(|((from, to), t)| format!("{}>{}={}", from, to, t.count));
+    let actions = stats
+        .actions
+        .iter()
+        .map(|((mode, variant), t)| format!("{}:{}={}", mode, variant, t.count));
+    switches.chain(actions).collect::<Vec<_>>().join(",")
+}
+
+fn parse_usage_counts(s: &str) -> UsageStats {
+    let mut stats = UsageStats::default();
+    for item in s.split(',') {
+        let Some((key, count)) = item.split_once('=') else {
+            continue;
+        };
+        let Ok(count) = count.parse() else {
+            continue;
+        };
+        let counts = TransitionStats {
+            count,
+            last_used: 0,
+        };
+        if let Some((from, to)) = key.split_once('>') {
+            stats
+                .transitions
+                .insert((from.to_string(), to.to_string()), counts);
+        } else if let Some((mode, variant)) = key.split_once(':') {
+            stats
+                .actions
+                .insert((mode.to_string(), variant.to_string()), counts);
+        }
+    }
+    stats
+}
+
+/// What the controller last saw of the tabs: enough to tell a new, closed
+/// or switched tab and the tab-wide toggles apart.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct TabsSeen {
+    count: usize,
+    active: usize,
+    fullscreen: bool,
+    /// Floating panes shown, and how many selectable ones there are: the
+    /// popup showing or closing flips the flag too, but never as a toggle
+    /// of the same panes.
+    floating: bool,
+    floating_panes: usize,
+    sync: bool,
+}
+
+impl TabsSeen {
+    fn of(tabs: &[TabInfo]) -> Option<Self> {
+        let tab = tabs.iter().find(|t| t.active)?;
+        Some(TabsSeen {
+            count: tabs.len(),
+            active: tab.position,
+            fullscreen: tab.is_fullscreen_active,
+            floating: tab.are_floating_panes_visible,
+            floating_panes: tab.selectable_floating_panes_count,
+            sync: tab.is_sync_panes_active,
+        })
+    }
+}
+
+/// What the controller last saw of the active tab's terminal panes: how many
+/// there are and which has focus, as `(floating, plugin, id)`. Plugin panes
+/// are left out, so our own popup coming and going isn't a new pane.
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct PanesSeen {
+    tab: usize,
+    panes: usize,
+    focused: Option<(bool, bool, u32)>,
+}
+
+impl PanesSeen {
+    fn of(tabs: &[TabInfo], manifest: &PaneManifest) -> Option<Self> {
+        let tab = tabs.iter().find(|t| t.active)?.position;
+        let panes: Vec<&PaneInfo> = manifest
+            .panes
+            .get(&tab)?
+            .iter()
+            .filter(|p| p.is_selectable && !p.is_suppressed && !p.is_plugin)
+            .collect();
+        let focused = panes
+            .iter()
+            .filter(|p| p.is_focused)
+            .max_by_key(|p| p.is_floating)
+            .map(|p| (p.is_floating, p.is_plugin, p.id));
+        Some(PanesSeen {
+            tab,
+            panes: panes.len(),
+            focused,
+        })
+    }
+}
+
+/// The action variants that could explain a change in the tabs.
+fn tab_actions(before: &TabsSeen, after: &TabsSeen) -> Vec<&'static str> {
+    if after.count != before.count {
+        return vec![if after.count > before.count {
+            "NewTab"
+        } else {
+            "CloseTab"
+        }];
+    }
+    let mut out = Vec::new();
+    if after.active != before.active {
+        let count = after.count.max(1);
+        if (before.active + 1) % count == after.active {
+            out.extend(["GoToNextTab", "MoveFocusOrTab"]);
+        } else if (after.active + 1) % count == before.active {
+            out.extend(["GoToPreviousTab", "MoveFocusOrTab"]);
+        } else {
+            out.extend(["GoToTab", "ToggleTab"]);
+        }
+    }
+    for (changed, variant) in [
+        (
+            after.fullscreen != before.fullscreen,
+            "ToggleFocusFullscreen",
+        ),
+        (
+            after.floating != before.floating
+                && after.floating_panes == before.floating_panes
+                && after.floating_panes > 0,
+            "ToggleFloatingPanes",
+        ),
+        (after.sync != before.sync, "ToggleActiveSyncTab"),
+    ] {
+        if changed {
+            out.push(variant);
+        }
+    }
+    out
+}
+
+/// The action variants that could explain a change in the active tab's
+/// panes. Focus moving between the tiled and floating layers comes with
+/// `ToggleFloatingPanes`, which `tab_actions` counts.
+fn pane_actions(before: &PanesSeen, after: &PanesSeen) -> Vec<&'static str> {
+    if after.tab != before.tab {
+        return Vec::new();
+    }
+    if after.panes > before.panes {
+        return vec!["NewPane"];
+    }
+    if after.panes < before.panes {
+        return vec!["CloseFocus"];
+    }
+    match (before.focused, after.focused) {
+        (Some(b), Some(a)) if a != b && a.0 == b.0 => vec![
+            "MoveFocus",
+            "MoveFocusOrTab",
+            "FocusNextPane",
+            "FocusPreviousPane",
+            "SwitchFocus",
+        ],
+        _ => Vec::new(),
+    }
+}
+
+/// Reorder a mode's entries most used first. An entry's count is how often
+/// its action (or any it merges) was inferred in `mode`, plus for a switch
+/// to another mode how often `mode` was left for it. Switches back to the
+/// base mode end every visit and say nothing about which key was used, so
+/// they don't count. Pinned entries stay on top, and the sort is stable:
+/// ties (and a cold start) keep the priority order.
+fn sort_by_usage(entries: &mut [Entry], mode: InputMode, base_mode: InputMode, usage: &UsageStats) {
+    let count = |entry: &Entry| {
+        let switches = switch_target(&entry.actions)
+            .filter(|to| *to != base_mode && *to != mode)
+            .and_then(|to| usage.get(mode, to))
+            .map_or(0, |t| t.count);
+        // "Focus h/j/k/l" merges four MoveFocus bindings: count it once.
+        let variants: std::collections::BTreeSet<String> = std::iter::once(&entry.actions)
+            .chain(&entry.merged)
+            .filter_map(|actions| actions.first())
+            .map(action_variant)
+            .collect();
+        let actions: u64 = variants
+            .iter()
+            .filter_map(|variant| usage.get_action(mode, variant))
+            .map(|t| t.count)
+            .sum();
+        switches + actions
+    };
+    entries.sort_by_key(|e| (e.pin.unwrap_or(usize::MAX), std::cmp::Reverse(count(e))));
+}
+
+/// Seconds since the Unix epoch (WASI provides the clock).
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// `now_secs` to the sub-second, for timer countdowns.
+fn now_secs_f64() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+/// A coarse age like "3d ago" or "5h ago".
+fn format_age(secs: u64) -> String {
+    if secs >= DAY_SECS {
+        format!("{}d ago", secs / DAY_SECS)
+    } else if secs >= 3600 {
+        format!("{}h ago", secs / 3600)
+    } else {
+        "just now".to_string()
+    }
+}
+
+/// The mode a binding's action chain ends up in, if it switches modes.
+fn switch_target(actions: &[Action]) -> Option<InputMode> {
+    actions.iter().rev().find_map(|action| match action {
+        Action::SwitchToMode(mode) => Some(*mode),
+        _ => None,
+    })
+}
+
+/// Per-mode entry counts, shared by every controller through `/data`.
+#[derive(Default)]
+struct Learning {
+    counts: BTreeMap<String, u64>,
+}
+
+impl Learning {
+    fn load() -> Self {
+        let counts = std::fs::read_to_string(LEARNING_PATH)
+            .map(|s| parse_counts(&s))
+            .unwrap_or_default();
+        Self { counts }
+    }
+
+    fn count(&self, mode: InputMode) -> u64 {
+        self.counts
+            .get(&format!("{:?}", mode))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Count an entry into `mode`. Re-reads the file first so controllers on
+    /// other clients add to the same totals instead of overwriting them.
+    fn record(&mut self, mode: InputMode) {
+        if let Ok(s) = std::fs::read_to_string(LEARNING_PATH) {
+            self.counts = parse_counts(&s);
+        }
+        *self.counts.entry(format!("{:?}", mode)).or_default() += 1;
+        self.save();
+    }
+
+    fn reset(&mut self) {
+        self.counts.clear();
+        self.save();
+    }
+
+    /// Best effort, like the query history.
+    fn save(&self) {
+        let _ = std::fs::write(LEARNING_PATH, format_counts(&self.counts));
+    }
+}
+
+/// `name<TAB>count` per line; malformed lines are skipped.
+fn parse_counts(s: &str) -> BTreeMap<String, u64> {
+    s.lines()
+        .filter_map(|line| {
+            let (name, count) = line.split_once('\t')?;
+            Some((name.to_string(), count.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn format_counts(counts: &BTreeMap<String, u64>) -> String {
+    counts
+        .iter()
+        .map(|(name, count)| format!("{}\t{}\n", name, count))
+        .collect()
+}
+
+/// The popup delay for a mode entered `uses` times: `delay` until the
+/// threshold, then growing with use up to `LEARN_MAX_DELAY_FACTOR` times it.
+fn learned_delay(delay: f64, uses: u64, threshold: u64) -> f64 {
+    if uses < threshold {
+        return delay;
+    }
+    let factor = 1.0 + (uses - threshold) as f64 / threshold as f64;
+    delay * factor.min(LEARN_MAX_DELAY_FACTOR)
+}
+
+/// One query per line; blank lines are skipped and only the newest
+/// `HISTORY_MAX` are kept.
+fn parse_history(s: &str) -> Vec<String> {
+    let entries: Vec<String> = s
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    let excess = entries.len().saturating_sub(HISTORY_MAX);
+    entries[excess..].to_vec()
+}
+
+/// One row of the popup: every key bound to a single action, plus its label.
+/// `actions` is the full chain of the first binding, for the browser's detail pane.
+/// `pin` is the entry's place in the `pin` list, if pinned; `hidden` marks
+/// bindings the user hid, kept only for the browser.
+struct Entry {
+    priority: u8,
+    pin: Option<usize>,
+    hidden: bool,
+    /// Popup: the focused tab makes this a no-op; drawn dimmed.
+    inapplicable: bool,
+    /// Popup: a preview row of the mode the entry above switches to.
+    nested: bool,
+    keys: Vec<String>,
+    label: String,
+    actions: Vec<Action>,
+    /// The action chains of rows folded into this one (tab jumps, the other
+    /// directions of a compacted family).
+    merged: Vec<Vec<Action>>,
+}
+
+impl Entry {
+    fn keys_str(&self) -> String {
+        self.keys.join(" ")
+    }
+
+    /// Pinned entries first (in pin order), then by priority.
+    fn order_key(&self) -> (usize, u8) {
+        (self.pin.unwrap_or(usize::MAX), self.priority)
+    }
+}
+
+/// One row of the browser: an `Entry` tagged with its mode and search score.
+/// `global` marks a binding that works in every mode (shown under "Global").
+/// `stats` is the recorded usage, in the usage views.
+struct BrowserRow {
+    mode: InputMode,
+    global: bool,
+    score: i32,
+    entry: Entry,
+    stats: Option<TransitionStats>,
+}
+
+impl BrowserRow {
+    fn new(mode: InputMode, global: bool, entry: Entry) -> Self {
+        Self {
+            mode,
+            global,
+            score: 0,
+            entry,
+            stats: None,
+        }
+    }
+
+    fn count(&self) -> u64 {
+        self.stats.map_or(0, |s| s.count)
+    }
+
+    /// When it was last used; `None` (never) sorts first.
+    fn last_used(&self) -> Option<u64> {
+        self.stats.map(|s| s.last_used)
+    }
+
+    fn mode_label(&self) -> String {
+        if self.global {
+            "Global".to_string()
+        } else {
+            mode_title(self.mode).to_string()
+        }
+    }
+}
+
+/// How the browser selection moves on a navigation key or mouse wheel, by a
+/// number of rows or to either end.
+/// (Named `Nav` to avoid shadowing `zellij_tile::prelude::Direction`.)
+enum Nav {
+    Up(usize),
+    Down(usize),
+    Top,
+    Bottom,
+}
+
+/// The selection after applying `nav` to `selected` in a list of `len` rows.
+fn nav_target(selected: usize, nav: Nav, len: usize) -> usize {
+    let last = len.saturating_sub(1);
+    match nav {
+        Nav::Up(n) => selected.saturating_sub(n),
+        Nav::Down(n) => selected.saturating_add(n).min(last),
+        Nav::Top => 0,
+        Nav::Bottom => last,
+    }
+}
+
+/// Short and heading names for a mode: the browser's mode column uses the
+/// short one, the popup title and "switch to" labels the heading.
+fn mode_names(mode: InputMode) -> (&'static str, &'static str) {
+    match mode {
+        InputMode::Normal => ("Normal", "Normal mode"),
+        InputMode::Locked => ("Locked", "Locked mode"),
+        InputMode::Resize => ("Resize", "Resize mode"),
+        InputMode::Pane => ("Pane", "Pane mode"),
+        InputMode::Tab => ("Tab", "Tab mode"),
+        InputMode::Scroll => ("Scroll", "Scroll mode"),
+        InputMode::EnterSearch => ("Search entry", "Search (type query)"),
+        InputMode::Search => ("Search", "Search mode"),
+        InputMode::RenameTab => ("Rename tab", "Rename tab"),
+        InputMode::RenamePane => ("Rename pane", "Rename pane"),
+        InputMode::Session => ("Session", "Session mode"),
+        InputMode::Move => ("Move", "Move pane mode"),
+        InputMode::Prompt => ("Prompt", "Prompt mode"),
+        InputMode::Tmux => ("Tmux", "Tmux mode"),
+    }
+}
+
+fn mode_title(mode: InputMode) -> &'static str {
+    mode_names(mode).0
+}
+
+fn mode_heading(mode: InputMode) -> &'static str {
+    mode_names(mode).1
+}
+
+/// The popup title: the mode's heading, or the path to it ("Tmux › Pane")
+/// when it was reached through other modes. A path that doesn't end at
+/// `mode` is stale and ignored.
+fn breadcrumb(path: &[InputMode], mode: InputMode) -> String {
+    if path.len() < 2 || path.last() != Some(&mode) {
+        return mode_heading(mode).to_string();
+    }
+    path.iter()
+        .map(|m| mode_title(*m))
+        .collect::<Vec<_>>()
+        .join(" › ")
+}
+
+/// The mode before `mode` on the path, if the path is current.
+fn previous_mode(path: &[InputMode], mode: InputMode) -> Option<InputMode> {
+    match path {
+        [.., previous, last] if *last == mode => Some(*previous),
+        _ => None,
+    }
+}
+
+/// Follow a mode switch: base mode clears the path, a mode already on it
+/// (going back) cuts the path there, anything else is appended.
+fn advance_mode_path(path: &mut Vec<InputMode>, mode: InputMode, base_mode: InputMode) {
+    if mode == base_mode {
+        path.clear();
+    } else if let Some(i) = path.iter().position(|m| *m == mode) {
+        path.truncate(i + 1);
+    } else {
+        path.push(mode);
+    }
+}
+
+fn format_mode_path(path: &[InputMode]) -> String {
+    path.iter()
+        .map(|m| format!("{:?}", m))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_mode_path(s: &str) -> Vec<InputMode> {
+    s.split(',').filter_map(|m| m.parse().ok()).collect()
+}
+
+/// A mode's config-key spelling: `EnterSearch` -> `enter_search`.
+fn mode_key(mode: InputMode) -> String {
+    let mut out = String::new();
+    for (i, c) in format!("{:?}", mode).chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+/// Display order for modes in the browser, base mode last.
+fn mode_rank(mode: InputMode) -> u8 {
+    match mode {
+        InputMode::Pane => 0,
+        InputMode::Tab => 1,
+        InputMode::Resize => 2,
+        InputMode::Move => 3,
+        InputMode::Scroll | InputMode::Search | InputMode::EnterSearch => 4,
+        InputMode::Session => 5,
+        InputMode::RenamePane | InputMode::RenameTab => 6,
+        InputMode::Normal | InputMode::Locked => 9,
+        _ => 7,
+    }
+}
+
+/// Case-insensitive subsequence fuzzy match. Returns `None` if `query`'s chars
+/// don't all appear in order; otherwise a score that rewards contiguous runs
+/// and matches at word boundaries. An empty query matches everything (score 0).
+fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let mut q = query.chars().map(|c| c.to_ascii_lowercase()).peekable();
+    let mut score = 0i32;
+    let mut run = 0i32;
+    let mut prev_boundary = true;
+    let mut next = q.next();
+    for th in text.chars() {
+        let Some(qc) = next else { break };
+        let boundary = matches!(th, ' ' | '+' | '-' | '_' | '(');
+        if th.to_ascii_lowercase() == qc {
+            score += 1 + run * 2 + if prev_boundary { 3 } else { 0 };
+            run += 1;
+            next = q.next();
+        } else {
+            run = 0;
+        }
+        prev_boundary = boundary;
+    }
+    if next.is_none() && q.peek().is_none() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+struct Layout {
+    pane_cols: usize,
+    pane_rows: usize,
+    keys_col: usize,
+    label_col: usize,
+    visible: usize,
+    overflow: usize,
+    /// Whether the globals section fits in full; if not it collapses to a
+    /// single "+N global" line.
+    globals_shown: bool,
+}
+
+/// Which bindings `group_bindings` keeps and how it orders them.
+#[derive(Default, Clone)]
+struct Grouping {
+    /// Include raw `Write`/`WriteChars` bindings (toggled live in the browser).
+    show_noise: bool,
+    /// `priority` config: label or `Action` variant name -> priority,
+    /// replacing `action_priority` for matching entries.
+    priorities: Vec<(String, u8)>,
+    /// `pin` config: labels, variant names or keys forced to the top, in
+    /// this order.
+    pins: Vec<String>,
+    /// `hide_actions` / `hide_keys` config: bindings to leave out, by action
+    /// (label or variant name) or by key, optionally for one mode only.
+    hide_actions: Vec<Scoped>,
+    hide_keys: Vec<Scoped>,
+    /// Keep hidden bindings as separate entries marked `hidden` instead of
+    /// dropping them.
+    keep_hidden: bool,
+}
+
+impl Grouping {
+    fn priority(&self, label: &str, actions: &[Action]) -> Option<u8> {
+        self.priorities
+            .iter()
+            .find(|(name, _)| names_action(name, label, actions))
+            .map(|(_, priority)| *priority)
+    }
+
+    fn is_hidden(&self, key: &str, label: &str, actions: &[Action], mode: InputMode) -> bool {
+        self.hide_actions
+            .iter()
+            .any(|h| h.applies_to(mode) && names_action(&h.name, label, actions))
+            || self
+                .hide_keys
+                .iter()
+                .any(|h| h.applies_to(mode) && h.name == key)
+    }
+
+    /// Position in the pin list, for entries that are pinned.
+    fn pin(&self, entry: &Entry) -> Option<usize> {
+        self.pins.iter().position(|name| {
+            names_action(name, &entry.label, &entry.actions) || entry.keys.contains(name)
+        })
+    }
+}
+
+/// Whether a user-supplied `name` refers to this action, either by its label
+/// ("Close pane") or by its `Action` variant name ("CloseFocus"), ignoring case.
+fn names_action(name: &str, label: &str, actions: &[Action]) -> bool {
+    name.eq_ignore_ascii_case(label)
+        || actions
+            .first()
+            .is_some_and(|action| name.eq_ignore_ascii_case(&action_variant(action)))
+}
+
+/// The bare variant name of an action: `NewPane(Some(Down), ..)` -> `NewPane`.
+fn action_variant(action: &Action) -> String {
+    format!("{:?}", action)
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect()
+}
+
+/// A config list item that may be limited to one mode with a `Mode:` prefix,
+/// e.g. `Session:Quit`. The mode is spelled as in zellij (`EnterSearch`) or
+/// as in `desc_<mode>` keys (`enter_search`).
+#[derive(Clone, Debug, PartialEq)]
+struct Scoped {
+    mode: Option<String>,
+    name: String,
+}
+
+impl Scoped {
+    fn applies_to(&self, mode: InputMode) -> bool {
+        self.mode.as_ref().is_none_or(|m| {
+            m.eq_ignore_ascii_case(&format!("{:?}", mode))
+                || m.eq_ignore_ascii_case(&mode_key(mode))
+        })
+    }
+}
+
+/// A comma-separated list of optionally mode-scoped names. Only a prefix of
+/// letters and underscores counts as a mode, so keys like `Ctrl+:` stay
+/// intact.
+fn parse_scoped(s: &str) -> Vec<Scoped> {
+    parse_list(s)
+        .into_iter()
+        .map(|item| match item.split_once(':') {
+            Some((mode, name))
+                if !mode.is_empty()
+                    && !name.trim().is_empty()
+                    && mode.chars().all(|c| c.is_ascii_alphabetic() || c == '_') =>
+            {
+                Scoped {
+                    mode: Some(mode.to_string()),
+                    name: name.trim().to_string(),
+                }
+            }
+            _ => Scoped {
+                mode: None,
+                name: item,
+            },
+        })
+        .collect()
+}
+
+fn format_scoped(items: &[Scoped]) -> String {
+    items
+        .iter()
+        .map(|item| match &item.mode {
+            Some(mode) => format!("{}:{}", mode, item.name),
+            None => item.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A comma-separated config list, trimmed, blanks dropped.
+fn parse_list(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// `name=priority` pairs from a comma-separated list; bad pairs are skipped.
+fn parse_priorities(s: &str) -> Vec<(String, u8)> {
+    parse_list(s)
+        .iter()
+        .filter_map(|item| {
+            let (name, priority) = item.split_once('=')?;
+            Some((name.trim().to_string(), priority.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// What the popup knows about the focused tab: its `TabInfo`, its panes from
+/// the latest `PaneUpdate`, and how many tabs there are.
+struct TabContext<'a> {
+    tab: &'a TabInfo,
+    panes: &'a [PaneInfo],
+    tab_count: usize,
+    /// Our own plugin pane, which never counts as the focused one.
+    own_id: u32,
+}
+
+impl<'a> TabContext<'a> {
+    /// A one-line summary: name, pane count, and whichever of sync /
+    /// fullscreen / floating panes are active.
+    fn summary(&self) -> String {
+        let tab = self.tab;
+        let panes = tab.selectable_tiled_panes_count + tab.selectable_floating_panes_count;
+        let mut line = format!(
+            "{} · {} pane{}",
+            tab.name,
+            panes,
+            if panes == 1 { "" } else { "s" }
+        );
+        for (active, flag) in [
+            (tab.is_sync_panes_active, "sync"),
+            (tab.is_fullscreen_active, "fullscreen"),
+            (tab.are_floating_panes_visible, "floating"),
+        ] {
+            if active {
+                line.push_str(" · ");
+                line.push_str(flag);
+            }
+        }
+        line
+    }
+
+    /// The pane with focus: the focused floating pane while another floating
+    /// pane has focus, else the focused tiled one. Our own pane (`own_id`)
+    /// never counts.
+    fn focused_pane(&self) -> Option<&'a PaneInfo> {
+        let floating = self.floating_focus();
+        self.panes.iter().find(|p| {
+            p.is_focused && !p.is_suppressed && p.is_floating == floating && !self.is_own(p)
+        })
+    }
+
+    fn is_own(&self, pane: &PaneInfo) -> bool {
+        pane.is_plugin && pane.id == self.own_id
+    }
+
+    /// Whether a floating pane other than ours has focus. The popup is a
+    /// floating pane itself, so the tab's floating flag alone doesn't say.
+    fn floating_focus(&self) -> bool {
+        self.tab.are_floating_panes_visible
+            && self
+                .panes
+                .iter()
+                .any(|p| p.is_floating && p.is_focused && !p.is_suppressed && !self.is_own(p))
+    }
+
+    /// Whether pane frames are drawn, judged by the focused tiled pane's
+    /// content being inset from its edges; `None` before a `PaneUpdate`.
+    fn frames_visible(&self) -> Option<bool> {
+        let pane = self
+            .panes
+            .iter()
+            .find(|p| p.is_focused && !p.is_floating && !p.is_suppressed)?;
+        Some(pane.pane_content_x != pane.pane_x || pane.pane_content_y != pane.pane_y)
+    }
+
+    /// Whether the toggle an action flips is currently on, for the toggles
+    /// we can observe; `None` for anything else.
+    fn toggle_state(&self, actions: &[Action]) -> Option<bool> {
+        match actions.first()? {
+            Action::ToggleFloatingPanes => Some(self.tab.are_floating_panes_visible),
+            Action::ToggleActiveSyncTab => Some(self.tab.is_sync_panes_active),
+            Action::ToggleFocusFullscreen => Some(self.tab.is_fullscreen_active),
+            Action::TogglePaneFrames => self.frames_visible(),
+            _ => None,
+        }
+    }
+
+    /// A toggle's label with its current state and the one a press leads to
+    /// ("Floating: on → off"); `None` for chains and other actions.
+    fn toggle_label(&self, actions: &[Action]) -> Option<String> {
+        if actions.len() != 1 {
+            return None;
+        }
+        let name = match actions[0] {
+            Action::ToggleFloatingPanes => "Floating",
+            Action::ToggleActiveSyncTab => "Sync",
+            Action::ToggleFocusFullscreen => "Fullscreen",
+            Action::TogglePaneFrames => "Frames",
+            _ => return None,
+        };
+        let on = self.toggle_state(actions)?;
+        let (now, next) = if on { ("on", "off") } else { ("off", "on") };
+        Some(format!("{name}: {now} → {next}"))
+    }
+
+    /// Whether an action would do anything in this tab. Unknown cases (no
+    /// panes yet, a floating or fullscreen focus) count as applicable, so
+    /// nothing is greyed out on a guess.
+    fn applies(&self, actions: &[Action]) -> bool {
+        let Some(action) = actions.first() else {
+            return true;
+        };
+        let tiled = self.tab.selectable_tiled_panes_count;
+        let focusable = tiled
+            + if self.tab.are_floating_panes_visible {
+                self.tab.selectable_floating_panes_count
+            } else {
+                0
+            };
+        match action {
+            Action::CloseTab
+            | Action::GoToNextTab
+            | Action::GoToPreviousTab
+            | Action::ToggleTab
+            | Action::MoveTab(_) => self.tab_count > 1,
+            Action::GoToTab(n) => (*n as usize) <= self.tab_count,
+            Action::NextSwapLayout | Action::PreviousSwapLayout => {
+                self.tab.active_swap_layout_name.is_some()
+            }
+            Action::MoveFocus(d) | Action::MovePane(Some(d)) => {
+                self.has_neighbor(*d) != Some(false)
+            }
+            Action::MoveFocusOrTab(d) => {
+                (self.tab_count > 1 && matches!(d, Direction::Left | Direction::Right))
+                    || self.has_neighbor(*d) != Some(false)
+            }
+            Action::MovePane(None) => tiled > 1,
+            Action::FocusNextPane | Action::FocusPreviousPane | Action::SwitchFocus => {
+                focusable > 1
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether a tiled pane lies beside the focused one in `dir`; `None` when
+    /// focus isn't on a tiled pane we can see.
+    fn has_neighbor(&self, dir: Direction) -> Option<bool> {
+        if self.floating_focus() || self.tab.is_fullscreen_active {
+            return None;
+        }
+        let tiled = || {
+            self.panes
+                .iter()
+                .filter(|p| !p.is_floating && !p.is_suppressed && p.is_selectable)
+        };
+        let f = tiled().find(|p| p.is_focused)?;
+        let rows_overlap =
+            |p: &PaneInfo| p.pane_y < f.pane_y + f.pane_rows && f.pane_y < p.pane_y + p.pane_rows;
+        let cols_overlap = |p: &PaneInfo| {
+            p.pane_x < f.pane_x + f.pane_columns && f.pane_x < p.pane_x + p.pane_columns
+        };
+        Some(tiled().any(|p| match dir {
+            Direction::Left => p.pane_x + p.pane_columns <= f.pane_x && rows_overlap(p),
+            Direction::Right => p.pane_x >= f.pane_x + f.pane_columns && rows_overlap(p),
+            Direction::Up => p.pane_y + p.pane_rows <= f.pane_y && cols_overlap(p),
+            Direction::Down => p.pane_y >= f.pane_y + f.pane_rows && cols_overlap(p),
+        }))
+    }
+}
+
+/// What to type in a mode whose keystrokes become text, or `None` for the
+/// other modes.
+fn text_entry_prompt(mode: InputMode) -> Option<&'static str> {
+    match mode {
+        InputMode::RenamePane => Some("Type the new pane name"),
+        InputMode::RenameTab => Some("Type the new tab name"),
+        InputMode::EnterSearch => Some("Type a search query"),
+        _ => None,
+    }
+}
+
+/// The mode a text entry mode is opened from (and returns to on cancel).
+fn text_entry_origin(mode: InputMode) -> Option<InputMode> {
+    match mode {
+        InputMode::RenamePane => Some(InputMode::Pane),
+        InputMode::RenameTab => Some(InputMode::Tab),
+        InputMode::EnterSearch => Some(InputMode::Scroll),
+        _ => None,
+    }
+}
+
+/// Confirm / cancel entries for a text entry mode, derived from its bindings.
+/// A binding that undoes the rename or (in search) goes back to Scroll mode
+/// cancels; a switch to the base mode, the mode the rename was opened from,
+/// or (in search) Search mode confirms. Zellij confirms a rename on Enter
+/// without a binding, so `↵` is listed unless Enter is bound to something.
+/// Bindings whose signature is in `exclude` (globals) are dropped; the rest
+/// are grouped as usual after the two.
+fn text_entry_entries(
+    binds: &[(KeyWithModifier, Vec<Action>)],
+    mode: InputMode,
+    base_mode: InputMode,
+    exclude: &std::collections::HashSet<String>,
+    grouping: &Grouping,
+) -> Vec<Entry> {
+    let new_entry = |label: &str, priority: u8| Entry {
+        priority,
+        pin: None,
+        hidden: false,
+        inapplicable: false,
+        nested: false,
+        keys: Vec::new(),
+        label: label.to_string(),
+        actions: Vec::new(),
+        merged: Vec::new(),
+    };
+    let cancel_label = if mode == InputMode::EnterSearch {
+        "Cancel"
+    } else {
+        "Cancel (undo rename)"
+    };
+    let mut confirm = new_entry("Confirm", 0);
+    let mut cancel = new_entry(cancel_label, 1);
+    if mode != InputMode::EnterSearch
+        && !binds.iter().any(|(key, _)| key.bare_key == BareKey::Enter)
+    {
+        confirm
+            .keys
+            .push(format_key(&KeyWithModifier::new(BareKey::Enter)));
+    }
+
+    let confirms = |target: InputMode| {
+        target == base_mode
+            || if mode == InputMode::EnterSearch {
+                target == InputMode::Search
+            } else {
+                Some(target) == text_entry_origin(mode)
+            }
+    };
+
+    let mut rest = Vec::new();
+    for (key, actions) in binds {
+        if exclude.contains(&binding_signature(key, actions, base_mode)) {
+            continue;
+        }
+        let undo = actions
+            .iter()
+            .any(|a| matches!(a, Action::UndoRenamePane | Action::UndoRenameTab));
+        let target = switch_target(actions);
+        let entry = if undo || (mode == InputMode::EnterSearch && target == Some(InputMode::Scroll))
+        {
+            &mut cancel
+        } else if target.is_some_and(confirms) {
+            &mut confirm
+        } else {
+            rest.push((key.clone(), actions.clone()));
+            continue;
+        };
+        let key_str = format_key(key);
+        if !entry.keys.contains(&key_str) {
+            entry.keys.push(key_str);
+        }
+        if entry.actions.is_empty() {
+            entry.actions = actions.clone();
+        }
+    }
+
+    [confirm, cancel]
+        .into_iter()
+        .filter(|e| !e.keys.is_empty())
+        .chain(group_bindings(&rest, mode, base_mode, exclude, grouping))
+        .collect()
+}
+
+/// Group a mode's keybindings by action, ordered by pin then priority,
+/// dropping noise (unless `show_noise`), bindings the user hid (unless
+/// `keep_hidden`, which groups them apart and marks them), and any binding
+/// whose signature is in `exclude` (used to hide globals from the per-mode
+/// popup).
+fn group_bindings(
+    binds: &[(KeyWithModifier, Vec<Action>)],
+    mode: InputMode,
+    base_mode: InputMode,
+    exclude: &std::collections::HashSet<String>,
+    grouping: &Grouping,
+) -> Vec<Entry> {
+    let mut order: Vec<String> = Vec::new();
+    let mut by_label: HashMap<String, Entry> = HashMap::new();
+
+    for (key, actions) in binds {
+        if actions.is_empty()
+            || (!grouping.show_noise && is_noise(actions))
+            || exclude.contains(&binding_signature(key, actions, base_mode))
+        {
+            continue;
+        }
+        let label = format_action(actions, base_mode);
+        let key_str = format_key(key);
+        let hidden = grouping.is_hidden(&key_str, &label, actions, mode);
+        if hidden && !grouping.keep_hidden {
+            continue;
+        }
+        let group = if hidden {
+            format!("{}\thidden", label)
+        } else {
+            label.clone()
+        };
+        let entry = by_label.entry(group.clone()).or_insert_with(|| {
+            order.push(group);
+            Entry {
+                priority: grouping
+                    .priority(&label, actions)
+                    .unwrap_or_else(|| action_priority(actions, mode, base_mode)),
+                pin: None,
+                hidden,
+                inapplicable: false,
+                nested: false,
+                keys: Vec::new(),
+                label,
+                actions: actions.clone(),
+                merged: Vec::new(),
+            }
+        });
+        if !entry.keys.contains(&key_str) {
+            entry.keys.push(key_str);
+        }
+    }
+
+    let mut entries: Vec<Entry> = order
+        .into_iter()
+        .filter_map(|label| by_label.remove(&label))
+        .collect();
+    collapse_tab_jumps(&mut entries);
+    for entry in &mut entries {
+        entry.pin = grouping.pin(entry);
+    }
+    entries.sort_by(|a, b| {
+        a.order_key()
+            .cmp(&b.order_key())
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    entries
+}
+
+/// The tab a `GoToTab` entry jumps to, when that's all its key does (besides
+/// the usual switch back to the base mode).
+fn tab_jump(entry: &Entry) -> Option<u32> {
+    match entry.actions.first() {
+        Some(Action::GoToTab(n)) if entry.keys.len() == 1 && !entry.hidden => Some(*n),
+        _ => None,
+    }
+}
+
+/// Merge runs of single-key `GoToTab` entries for consecutive tabs on
+/// consecutive digit keys (`1`..`9` → tabs 1..9) into one "Go to tab N" row
+/// keyed `1-9`. Other keys stay on their own rows so the key-to-tab mapping
+/// stays readable (`0` → tab 10 after `1-9`).
+fn collapse_tab_jumps(entries: &mut Vec<Entry>) {
+    let mut jumps: Vec<(u32, usize)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, e)| tab_jump(e).map(|n| (n, i)))
+        .collect();
+    jumps.sort();
+    let mut merged = std::collections::HashSet::new();
+    let mut start = 0;
+    while start < jumps.len() {
+        let first = &entries[jumps[start].1];
+        let mut end = start + 1;
+        while end < jumps.len() {
+            let next = &entries[jumps[end].1];
+            let prev = &entries[jumps[end - 1].1];
+            if jumps[end].0 != jumps[end - 1].0 + 1
+                || !next_digit_key(&prev.keys[0], &next.keys[0])
+                || next.actions[1..] != first.actions[1..]
+            {
+                break;
+            }
+            end += 1;
+        }
+        if end - start > 1 {
+            let last_key = entries[jumps[end - 1].1].keys[0].clone();
+            let rest: Vec<Vec<Action>> = jumps[start + 1..end]
+                .iter()
+                .map(|&(_, i)| entries[i].actions.clone())
+                .collect();
+            let head = jumps[start].1;
+            let entry = &mut entries[head];
+            entry.keys = vec![key_range(&entry.keys[0], &last_key)];
+            entry.label = "Go to tab N".to_string();
+            entry.merged = rest;
+            merged.extend(jumps[start + 1..end].iter().map(|&(_, i)| i));
+        }
+        start = end;
+    }
+    *entries = std::mem::take(entries)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !merged.contains(i))
+        .map(|(_, e)| e)
+        .collect();
+}
+
+/// Insert, under each entry that switches to another mode, the first `limit`
+/// of that mode's entries (from `lookup`), indented and marked `nested`.
+/// Mode switches inside the preview are skipped so the tree stays one level
+/// deep, as are switches back to `mode`.
+fn nest_previews(
+    entries: Vec<Entry>,
+    mode: InputMode,
+    limit: usize,
+    lookup: impl Fn(InputMode) -> Vec<Entry>,
+) -> Vec<Entry> {
+    let mut out = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let target = match entry.actions.first() {
+            Some(Action::SwitchToMode(m)) if *m != mode && !entry.label.starts_with("Back to") => {
+                Some(*m)
+            }
+            _ => None,
+        };
+        out.push(entry);
+        let Some(target) = target else {
+            continue;
+        };
+        let preview = lookup(target)
+            .into_iter()
+            .filter(|e| !matches!(e.actions.first(), Some(Action::SwitchToMode(_))))
+            .take(limit)
+            .map(|mut e| {
+                e.keys = vec![format!("  {}", e.keys_str())];
+                e.nested = true;
+                e
+            });
+        out.extend(preview);
+    }
+    out
+}
+
+/// The verb and direction of a single directional action ("Focus", Left),
+/// for folding the four directions into one row.
+fn direction_family(entry: &Entry) -> Option<(&'static str, Direction)> {
+    if entry.hidden || entry.actions.len() != 1 {
+        return None;
+    }
+    match entry.actions[0] {
+        Action::MoveFocus(d) | Action::MoveFocusOrTab(d) => Some(("Focus", d)),
+        Action::MovePane(Some(d)) => Some(("Move pane", d)),
+        Action::Resize(Resize::Increase, Some(d)) => Some(("Grow", d)),
+        Action::Resize(Resize::Decrease, Some(d)) => Some(("Shrink", d)),
+        Action::MoveTab(d) => Some(("Move tab", d)),
+        _ => None,
+    }
+}
+
+/// Fold the direction variants of each verb into one row keyed like
+/// `h j k l / ←↓↑→`: keys listed left, down, up, right, one group per
+/// alternative binding. Left alone when the directions have different
+/// numbers of keys, since the key-to-direction mapping would be lost.
+fn compact_directions(entries: &mut Vec<Entry>) {
+    const ORDER: [Direction; 4] = [
+        Direction::Left,
+        Direction::Down,
+        Direction::Up,
+        Direction::Right,
+    ];
+    let mut verbs: Vec<&'static str> = Vec::new();
+    for (verb, _) in entries.iter().filter_map(direction_family) {
+        if !verbs.contains(&verb) {
+            verbs.push(verb);
+        }
+    }
+    let mut merged = std::collections::HashSet::new();
+    for verb in verbs {
+        let mut family: Vec<(usize, usize)> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| match direction_family(e) {
+                Some((v, d)) if v == verb => Some((ORDER.iter().position(|o| *o == d)?, i)),
+                _ => None,
+            })
+            .collect();
+        family.sort();
+        family.dedup_by_key(|(dir, _)| *dir);
+        if family.len() < 2 {
+            continue;
+        }
+        let width = entries[family[0].1].keys.len();
+        if family.iter().any(|&(_, i)| entries[i].keys.len() != width) {
+            continue;
+        }
+        // Letters before arrows within each direction, so the groups line up.
+        let keys: Vec<Vec<String>> = family
+            .iter()
+            .map(|&(_, i)| {
+                let mut keys = entries[i].keys.clone();
+                keys.sort_by_key(|k| is_arrow(k));
+                keys
+            })
+            .collect();
+        let groups: Vec<String> = (0..width)
+            .map(|g| {
+                let group: Vec<&str> = keys.iter().map(|k| k[g].as_str()).collect();
+                group.join(if group.iter().all(|k| is_arrow(k)) {
+                    ""
+                } else {
+                    " "
+                })
+            })
+            .collect();
+        let head = family.iter().map(|&(_, i)| i).min().unwrap_or(family[0].1);
+        let rest: Vec<Vec<Action>> = family
+            .iter()
+            .filter(|&&(_, i)| i != head)
+            .map(|&(_, i)| entries[i].actions.clone())
+            .collect();
+        merged.extend(family.iter().map(|&(_, i)| i).filter(|&i| i != head));
+        let entry = &mut entries[head];
+        entry.keys = vec![groups.join(" / ")];
+        entry.label = verb.to_string();
+        entry.merged = rest;
+    }
+    *entries = std::mem::take(entries)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !merged.contains(i))
+        .map(|(_, e)| e)
+        .collect();
+}
+
+fn is_arrow(key: &str) -> bool {
+    matches!(key, "←" | "↓" | "↑" | "→")
+}
+
+/// Whether key `next` is `prev` with its last digit one higher: `1` → `2`,
+/// `Alt+4` → `Alt+5`, but not `9` → `0` or `a` → `b`.
+fn next_digit_key(prev: &str, next: &str) -> bool {
+    let split = |key: &str| {
+        let mut chars = key.chars();
+        let digit = chars.next_back()?.to_digit(10)?;
+        Some((chars.as_str().to_string(), digit))
+    };
+    matches!(
+        (split(prev), split(next)),
+        (Some((a, x)), Some((b, y))) if a == b && y == x + 1
+    )
+}
+
+/// `1`, `9` → `1-9`; `Alt+1`, `Alt+9` → `Alt+1-9`.
+fn key_range(first: &str, last: &str) -> String {
+    let common = first
+        .char_indices()
+        .zip(last.chars())
+        .take_while(|((_, a), b)| a == b)
+        .last()
+        .map(|((i, c), _)| i + c.len_utf8())
+        .unwrap_or(0);
+    format!("{}-{}", first, &last[common..])
+}
+
+/// The tabs by number, for the collapsed "Go to tab N" row with `tab_names`:
+/// "1 editor · 2 logs · 3 db".
+fn tab_list(tabs: &[TabInfo]) -> String {
+    let mut tabs: Vec<&TabInfo> = tabs.iter().collect();
+    tabs.sort_by_key(|t| t.position);
+    tabs.iter()
+        .map(|t| format!("{} {}", t.position + 1, t.name))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// Where to put a `(width, height)` box in an `(x, y, cols, rows)` area:
+/// `position`'s corner unless something's in the way. Unselectable tiled
+/// plugin panes along the top or bottom edge (tab and status bars) shrink the
+/// area; then the corner overlapping the least of the visible floating panes
+/// wins, trying the configured corner, the other bottom corner, and the two
+/// top corners in that order. `own_id` is our own plugin pane, never in the way.
+fn place_box(
+    area: (usize, usize, usize, usize),
+    size: (usize, usize),
+    position: Position,
+    panes: &[PaneInfo],
+    floating_visible: bool,
+    own_id: u32,
+) -> (usize, usize) {
+    let (left, mut top, cols, rows) = area;
+    let (w, h) = size;
+    let mut bottom = top + rows;
+    let right = left + cols;
+    let others = panes
+        .iter()
+        .filter(|p| !p.is_suppressed && (!p.is_plugin || p.id != own_id));
+    for bar in others
+        .clone()
+        .filter(|p| p.is_plugin && !p.is_floating && !p.is_selectable)
+    {
+        let bar_bottom = bar.pane_y + bar.pane_rows;
+        if bar.pane_x >= right || bar.pane_x + bar.pane_columns <= left {
+            continue;
+        }
+        if bar.pane_y <= top && bar_bottom > top && bar_bottom < bottom {
+            top = bar_bottom;
+        } else if bar_bottom >= bottom && bar.pane_y > top && bar.pane_y < bottom {
+            bottom = bar.pane_y;
+        }
+    }
+    let x_right = right.saturating_sub(w + MARGIN).max(left);
+    let x_left = left + MARGIN;
+    let y_bottom = bottom.saturating_sub(h + MARGIN).max(top);
+    let y_top = top + MARGIN;
+    let (near, far) = match position {
+        Position::BottomRight => (x_right, x_left),
+        Position::BottomLeft => (x_left, x_right),
+    };
+    let candidates = [
+        (near, y_bottom),
+        (far, y_bottom),
+        (near, y_top),
+        (far, y_top),
+    ];
+    let overlap = |(x, y): (usize, usize)| -> usize {
+        others
+            .clone()
+            .filter(|p| p.is_floating && floating_visible)
+            .map(|p| {
+                let cols = (x + w)
+                    .min(p.pane_x + p.pane_columns)
+                    .saturating_sub(x.max(p.pane_x));
+                let rows = (y + h)
+                    .min(p.pane_y + p.pane_rows)
+                    .saturating_sub(y.max(p.pane_y));
+                cols * rows
+            })
+            .sum()
+    };
+    candidates
+        .into_iter()
+        .min_by_key(|c| overlap(*c))
+        .unwrap_or(candidates[0])
+}
+
+fn floating_coords(x: usize, y: usize, w: usize, h: usize) -> Option<FloatingPaneCoordinates> {
     FloatingPaneCoordinates::new(
         Some(x.to_string()),
         Some(y.to_string()),
@@ -751,296 +3706,1884 @@ fn floating_coords(x: usize, y: usize, w: usize, h: usize) -> Option<FloatingPan
     )
 }
 
-fn compute_layout(
-    entries: &[Entry],
-    display_cols: usize,
-    display_rows: usize,
-    max_height_pct: usize,
-) -> Layout {
-    let keys_col = entries
-        .iter()
-        .map(|e| display_width(&e.keys_str()))
-        .max()
-        .unwrap_or(0)
-        .clamp(1, KEYS_COL_MAX);
+/// Size the popup for `header_rows` of title/hints, `entries`, and an optional
+/// trailing `globals` section.
+/// The section gets a heading row and only appears when everything fits;
+/// otherwise it shrinks to one "+N global" line, which takes priority over
+/// the mode's own overflowing entries.
+fn compute_layout(
+    entries: &[Entry],
+    globals: &[Entry],
+    header_rows: usize,
+    display_cols: usize,
+    display_rows: usize,
+    max_height_pct: usize,
+) -> Layout {
+    let keys_col = entries
+        .iter()
+        .chain(globals)
+        .map(|e| display_width(&e.keys_str()))
+        .max()
+        .unwrap_or(0)
+        .clamp(1, KEYS_COL_MAX);
+
+    let max_inner = MAX_INNER_WIDTH.min(inner_width(display_cols));
+    let raw_label = entries
+        .iter()
+        .chain(globals)
+        .map(|e| display_width(&e.label))
+        .max()
+        .unwrap_or(1);
+    let label_col = raw_label.min(max_inner.saturating_sub(keys_col + 2)).max(1);
+
+    let inner = keys_col + 2 + label_col;
+    let pane_cols = (inner + 4)
+        .min(display_cols.saturating_sub(2 * MARGIN))
+        .max(8);
+
+    let cap_rows = (display_rows.saturating_mul(max_height_pct) / 100).max(MIN_BOX_ROWS);
+    // Subtract the border (2) and the header lines to get body capacity.
+    let body_avail = cap_rows.saturating_sub(2 + header_rows).max(1);
+
+    let section_rows = if globals.is_empty() {
+        0
+    } else {
+        1 + globals.len()
+    };
+    let globals_shown = section_rows > 0 && entries.len() + section_rows <= body_avail;
+    let globals_rows = if globals_shown {
+        section_rows
+    } else {
+        usize::from(!globals.is_empty())
+    };
+    let entries_avail = body_avail.saturating_sub(globals_rows).max(1);
+
+    let (visible, overflow) = if entries.len() <= entries_avail {
+        (entries.len(), 0)
+    } else {
+        // One body line goes to the "+N more" indicator.
+        let visible = entries_avail.saturating_sub(1).max(1);
+        (visible, entries.len() - visible)
+    };
+
+    let content_rows = header_rows + visible + usize::from(overflow > 0) + globals_rows;
+    let pane_rows = (content_rows + 2).max(MIN_BOX_ROWS);
+
+    Layout {
+        pane_cols,
+        pane_rows,
+        keys_col,
+        label_col,
+        visible,
+        overflow,
+        globals_shown,
+    }
+}
+
+/// Every base-mode key that enters another mode, as one line in the usual
+/// mode order: "Ctrl+p Pane · Ctrl+t Tab · Ctrl+n Resize". Several keys for
+/// one mode are joined with "/".
+fn base_summary(binds: &[(KeyWithModifier, Vec<Action>)], base_mode: InputMode) -> String {
+    let mut by_mode: BTreeMap<(u8, String), (InputMode, Vec<String>)> = BTreeMap::new();
+    for (key, actions) in binds {
+        let Some(Action::SwitchToMode(mode)) = actions.first() else {
+            continue;
+        };
+        if *mode == base_mode {
+            continue;
+        }
+        let (_, keys) = by_mode
+            .entry((mode_rank(*mode), format!("{:?}", mode)))
+            .or_insert_with(|| (*mode, Vec::new()));
+        let key = format_key(key);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    by_mode
+        .into_values()
+        .map(|(mode, mut keys)| {
+            keys.sort();
+            format!("{} {}", keys.join("/"), mode_title(mode))
+        })
+        .collect::<Vec<_>>()
+        .join(STRIP_SEP)
+}
+
+/// Size a box for the base-mode summary under `header_rows` of heading:
+/// `(pane_cols, pane_rows, wrapped lines)`, wrapping to the display width
+/// and cutting lines that don't fit its height.
+fn summary_box(
+    summary: &str,
+    header_rows: usize,
+    display_cols: usize,
+    display_rows: usize,
+) -> (usize, usize, Vec<String>) {
+    let max_inner = inner_width(display_cols).max(8);
+    let mut lines = wrap_to_width(summary, max_inner);
+    lines.truncate(
+        display_rows
+            .saturating_sub(header_rows + 2 + 2 * MARGIN)
+            .max(1),
+    );
+    let inner = lines
+        .iter()
+        .map(|l| display_width(l))
+        .max()
+        .unwrap_or(0)
+        .max(display_width(summary).min(max_inner));
+    let pane_cols = (inner + 4)
+        .min(display_cols.saturating_sub(2 * MARGIN))
+        .max(8);
+    let pane_rows = (header_rows + lines.len() + 2).max(MIN_BOX_ROWS);
+    (pane_cols, pane_rows, lines)
+}
+
+/// `layout "strip"`: which entries go on each row of the strip, and its size.
+struct Strip {
+    pane_cols: usize,
+    pane_rows: usize,
+    /// Usable width of a row.
+    width: usize,
+    /// Entry index ranges, one per row.
+    rows: Vec<std::ops::Range<usize>>,
+    overflow: usize,
+}
+
+/// Width of one strip item, `keys label`, capped at a full row.
+fn strip_item_width(entry: &Entry, width: usize) -> usize {
+    (display_width(&entry.keys_str()) + 1 + display_width(&entry.label)).min(width)
+}
+
+/// The "+N more" / "+N global" notes that end a strip's last row.
+fn strip_notes(overflow: usize, globals: usize) -> Vec<String> {
+    let mut notes = Vec::new();
+    if overflow > 0 {
+        notes.push(format!("+{} more", overflow));
+    }
+    if globals > 0 {
+        notes.push(format!("+{} global", globals));
+    }
+    notes
+}
+
+/// Flow `entries` from `start` on across full-width rows of
+/// `key label · key label · ...`, at most `max_rows` of them. Entries that
+/// don't fit are counted in `overflow`; room for that note (and a "+N global"
+/// one) is kept at the end of the last row.
+fn compute_strip(
+    entries: &[Entry],
+    start: usize,
+    globals: usize,
+    header_rows: usize,
+    display_cols: usize,
+    max_rows: usize,
+) -> Strip {
+    let width = inner_width(display_cols).max(8);
+    let sep = display_width(STRIP_SEP);
+    let row_width = |range: &std::ops::Range<usize>| -> usize {
+        entries[range.clone()]
+            .iter()
+            .map(|e| strip_item_width(e, width))
+            .sum::<usize>()
+            + sep * range.len().saturating_sub(1)
+    };
+
+    let mut rows = Vec::new();
+    let mut start = start.min(entries.len());
+    let mut used = 0;
+    for (i, entry) in entries.iter().enumerate().skip(start) {
+        let w = strip_item_width(entry, width);
+        if i > start && used + sep + w > width {
+            rows.push(start..i);
+            start = i;
+            used = w;
+        } else {
+            used += if i > start { sep + w } else { w };
+        }
+    }
+    rows.push(start..entries.len());
+    let max_rows = max_rows.max(1);
+    rows.truncate(max_rows);
+
+    // Make room for the notes on the last row, dropping entries or taking
+    // another row if allowed.
+    loop {
+        let last = rows.last().cloned().unwrap_or(0..0);
+        let overflow = entries.len() - last.end;
+        let notes: usize = strip_notes(overflow, globals)
+            .iter()
+            .map(|n| display_width(n) + sep)
+            .sum();
+        if notes == 0 || last.is_empty() || row_width(&last) + notes <= width {
+            break;
+        }
+        if rows.len() < max_rows {
+            rows.push(last.end..last.end);
+        } else if let Some(row) = rows.last_mut() {
+            row.end -= 1;
+        }
+    }
+
+    let shown = rows.last().map_or(0, |r| r.end);
+    let pane_cols = (width + 4)
+        .min(display_cols.saturating_sub(2 * MARGIN))
+        .max(8);
+    Strip {
+        pane_cols,
+        pane_rows: (header_rows + rows.len() + 2).max(MIN_BOX_ROWS),
+        width,
+        rows,
+        overflow: entries.len() - shown,
+    }
+}
+
+/// Where each page of a strip starts. Each page picks up after the last
+/// one's entries.
+fn strip_pages(
+    entries: &[Entry],
+    globals: usize,
+    header_rows: usize,
+    display_cols: usize,
+    max_rows: usize,
+) -> Vec<usize> {
+    let mut starts = vec![0];
+    loop {
+        let start = starts[starts.len() - 1];
+        let strip = compute_strip(entries, start, globals, header_rows, display_cols, max_rows);
+        let end = strip.rows.last().map_or(start, |r| r.end);
+        if strip.overflow == 0 || end == start {
+            break;
+        }
+        starts.push(end);
+    }
+    starts
+}
+
+/// Room for a box's content on a `display_cols` wide display.
+fn inner_width(display_cols: usize) -> usize {
+    display_cols.saturating_sub(BOX_FRAME_COLS)
+}
+
+/// The lines of a strip computed by `compute_strip`, under the popup's
+/// heading.
+fn strip_lines(
+    entries: &[Entry],
+    globals: usize,
+    heading: &[String],
+    strip: &Strip,
+) -> Vec<String> {
+    let header = Colour::Fixed(252).bold();
+    let keys_style = Colour::Fixed(75).bold();
+    let label_style = Colour::Fixed(250).normal();
+    let accent_back = Colour::Fixed(114).normal();
+    let accent_switch = Colour::Fixed(180).normal();
+    let dim = Colour::Fixed(244).normal();
+
+    let mut lines = Vec::new();
+    for (i, line) in heading.iter().enumerate() {
+        let line = truncate_to_width(line, strip.width);
+        let style = if i == 0 { header } else { dim };
+        lines.push(style.paint(line).to_string());
+    }
+
+    for (n, range) in strip.rows.iter().enumerate() {
+        let mut items: Vec<String> = entries[range.clone()]
+            .iter()
+            .map(|entry| {
+                let keys = truncate_to_width(&entry.keys_str(), strip.width);
+                let room = strip.width.saturating_sub(display_width(&keys) + 1);
+                let label = truncate_to_width(&entry.label, room);
+                let (keys_style, label_style) = if entry.inapplicable || entry.nested {
+                    (dim, dim)
+                } else if entry.label.starts_with("Back to") {
+                    (keys_style, accent_back)
+                } else if matches!(entry.actions.first(), Some(Action::SwitchToMode(_))) {
+                    (keys_style, accent_switch)
+                } else {
+                    (keys_style, label_style)
+                };
+                format!("{} {}", keys_style.paint(keys), label_style.paint(label))
+            })
+            .collect();
+        if n + 1 == strip.rows.len() {
+            items.extend(
+                strip_notes(strip.overflow, globals)
+                    .into_iter()
+                    .map(|note| dim.paint(note).to_string()),
+            );
+        }
+        lines.push(items.join(&dim.paint(STRIP_SEP).to_string()));
+    }
+    lines
+}
+
+/// Visible width of a string. Our key/label glyphs are all single-width, so a
+/// char count is exact here and avoids a unicode-width dependency.
+fn display_width(s: &str) -> usize {
+    s.chars().count()
+}
+
+/// Truncate to `max` columns with an ellipsis, never splitting a char.
+fn truncate_to_width(s: &str, max: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    if display_width(s) <= max {
+        return s.to_string();
+    }
+    let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// Hard-wrap to `width` columns, preferring to break after a space.
+fn wrap_to_width(s: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut rest: Vec<char> = s.chars().collect();
+    while rest.len() > width {
+        let cut = rest[..=width]
+            .iter()
+            .rposition(|c| *c == ' ')
+            .filter(|i| *i > 0)
+            .map_or(width, |i| i + 1);
+        lines.push(
+            rest[..cut]
+                .iter()
+                .collect::<String>()
+                .trim_end()
+                .to_string(),
+        );
+        rest.drain(..cut);
+    }
+    lines.push(rest.into_iter().collect());
+    lines
+}
+
+fn pad_right(s: &str, width: usize) -> String {
+    let w = display_width(s);
+    let mut out = s.to_string();
+    if w < width {
+        out.extend(std::iter::repeat_n(' ', width - w));
+    }
+    out
+}
+
+/// Stable identity for a `key -> action` binding, used to match the same
+/// binding across modes (e.g. to detect globals present in the base mode).
+fn binding_signature(key: &KeyWithModifier, actions: &[Action], base_mode: InputMode) -> String {
+    format!("{}\t{}", format_key(key), format_action(actions, base_mode))
+}
+
+fn is_noise(actions: &[Action]) -> bool {
+    match actions.first() {
+        None => true,
+        // Raw "type this into the terminal" bindings carry no useful hint.
+        Some(Action::Write(..)) | Some(Action::WriteChars(..)) => true,
+        _ => false,
+    }
+}
+
+fn format_key(key: &KeyWithModifier) -> String {
+    let mut result = String::new();
+    if key.has_modifiers(&[KeyModifier::Ctrl]) {
+        result.push_str("Ctrl+");
+    }
+    if key.has_modifiers(&[KeyModifier::Alt]) {
+        result.push_str("Alt+");
+    }
+    if key.has_modifiers(&[KeyModifier::Shift]) {
+        result.push_str("Shift+");
+    }
+    match key.bare_key {
+        BareKey::Char(c) => result.push(c),
+        BareKey::F(n) => result.push_str(&format!("F{}", n)),
+        BareKey::Enter => result.push('↵'),
+        BareKey::Esc => result.push_str("Esc"),
+        BareKey::Tab => result.push_str("Tab"),
+        BareKey::Backspace => result.push_str("Bksp"),
+        BareKey::Delete => result.push_str("Del"),
+        BareKey::Insert => result.push_str("Ins"),
+        BareKey::Home => result.push_str("Home"),
+        BareKey::End => result.push_str("End"),
+        BareKey::PageUp => result.push_str("PgUp"),
+        BareKey::PageDown => result.push_str("PgDn"),
+        BareKey::Up => result.push('↑'),
+        BareKey::Down => result.push('↓'),
+        BareKey::Left => result.push('←'),
+        BareKey::Right => result.push('→'),
+        _ => result.push('?'),
+    }
+    result
+}
+
+/// Bytes written to the terminal, caret-notation for control characters
+/// (`^L`, `Esc[A`), so a raw `Write` binding gets a readable label.
+fn format_bytes(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for &b in bytes {
+        match b {
+            0x1b => out.push_str("Esc"),
+            0x00..=0x1f => {
+                out.push('^');
+                out.push(char::from(b + 0x40));
+            }
+            0x7f => out.push_str("^?"),
+            0x20..=0x7e => out.push(char::from(b)),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out
+}
+
+fn format_action(actions: &[Action], base_mode: InputMode) -> String {
+    let Some(action) = actions.first() else {
+        return "—".to_string();
+    };
+    match action {
+        Action::Quit => "Quit zellij".to_string(),
+        Action::Write(_, bytes, _) => format!("Send {}", format_bytes(bytes)),
+        Action::WriteChars(chars) => format!("Type '{}'", chars),
+        Action::SwitchToMode(mode) => {
+            if *mode == base_mode {
+                "Back to normal".to_string()
+            } else {
+                mode_heading(*mode).to_string()
+            }
+        }
+        Action::Resize(resize, dir) => {
+            let verb = match resize {
+                Resize::Increase => "Grow",
+                Resize::Decrease => "Shrink",
+            };
+            match dir {
+                Some(d) => format!("{} {:?}", verb, d),
+                None => verb.to_string(),
+            }
+        }
+        Action::MoveFocus(d) | Action::MoveFocusOrTab(d) => format!("Focus {:?}", d),
+        Action::MovePane(Some(d)) => format!("Move pane {:?}", d),
+        Action::MovePane(None) => "Move pane".to_string(),
+        Action::NewPane(dir, ..) => match dir {
+            Some(d) => format!("New pane {:?}", d),
+            None => "New pane".to_string(),
+        },
+        Action::CloseFocus => "Close pane".to_string(),
+        Action::NewTab(..) => "New tab".to_string(),
+        Action::GoToTab(n) => format!("Go to tab {}", n),
+        Action::GoToNextTab => "Next tab".to_string(),
+        Action::GoToPreviousTab => "Previous tab".to_string(),
+        Action::CloseTab => "Close tab".to_string(),
+        Action::ToggleFloatingPanes => "Toggle floating".to_string(),
+        Action::TogglePaneFrames => "Toggle frames".to_string(),
+        Action::ToggleFocusFullscreen => "Fullscreen".to_string(),
+        Action::ToggleActiveSyncTab => "Sync tab".to_string(),
+        Action::ToggleTab => "Toggle tab".to_string(),
+        Action::PaneNameInput(..) => "Rename pane".to_string(),
+        Action::TabNameInput(..) => "Rename tab".to_string(),
+        Action::UndoRenamePane => "Undo rename".to_string(),
+        Action::UndoRenameTab => "Undo rename".to_string(),
+        Action::HalfPageScrollDown => "Half page down".to_string(),
+        Action::HalfPageScrollUp => "Half page up".to_string(),
+        Action::PageScrollDown => "Page down".to_string(),
+        Action::PageScrollUp => "Page up".to_string(),
+        Action::ScrollDown => "Scroll down".to_string(),
+        Action::ScrollUp => "Scroll up".to_string(),
+        Action::ScrollToBottom => "Scroll to bottom".to_string(),
+        Action::EditScrollback => "Edit scrollback".to_string(),
+        Action::Detach => "Detach".to_string(),
+        Action::Run(..) => "Run command".to_string(),
+        Action::LaunchOrFocusPlugin(..) | Action::LaunchPlugin(..) => "Launch plugin".to_string(),
+        Action::SwitchFocus => "Switch focus".to_string(),
+        Action::MoveTab(d) => format!("Move tab {:?}", d),
+        Action::NewStackedPane(..) => "New stacked pane".to_string(),
+        Action::TogglePaneEmbedOrFloating => "Embed / float pane".to_string(),
+        Action::NextSwapLayout => "Next layout".to_string(),
+        Action::PreviousSwapLayout => "Previous layout".to_string(),
+        Action::BreakPane => "Break pane to new tab".to_string(),
+        Action::BreakPaneRight => "Break pane right".to_string(),
+        Action::BreakPaneLeft => "Break pane left".to_string(),
+        Action::ToggleGroupMarking => "Mark pane group".to_string(),
+        Action::TogglePaneInGroup => "Toggle pane in group".to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Human name for the priority band an action falls in, shown in the browser's
+/// detail pane.
+fn action_category(actions: &[Action], base_mode: InputMode) -> &'static str {
+    match action_priority(actions, base_mode, base_mode) {
+        10 => "Create",
+        11 => "Close",
+        12 => "Focus",
+        13 => "Move",
+        14 => "Resize",
+        16 => "Break out",
+        20 => "Tabs",
+        25 => "Scroll",
+        30 => "Toggles",
+        32 => "Swap layouts",
+        34 => "Pane groups",
+        35 => "Rename",
+        40 => "Session",
+        45 => "Send input",
+        55 => "Switch mode",
+        60 => "Back to base",
+        65 => "Quit",
+        _ => "Other",
+    }
+}
+
+fn action_priority(actions: &[Action], _mode: InputMode, base_mode: InputMode) -> u8 {
+    let Some(action) = actions.first() else {
+        return 200;
+    };
+    match action {
+        Action::NewPane(..) | Action::NewTab(..) | Action::NewStackedPane(..) => 10,
+        Action::CloseFocus | Action::CloseTab => 11,
+        Action::MoveFocus(..) | Action::MoveFocusOrTab(..) | Action::SwitchFocus => 12,
+        Action::MovePane(..) => 13,
+        Action::Resize(..) => 14,
+        Action::BreakPane | Action::BreakPaneRight | Action::BreakPaneLeft => 16,
+        Action::GoToTab(..)
+        | Action::GoToNextTab
+        | Action::GoToPreviousTab
+        | Action::ToggleTab
+        | Action::MoveTab(..) => 20,
+        Action::ScrollUp
+        | Action::ScrollDown
+        | Action::PageScrollUp
+        | Action::PageScrollDown
+        | Action::HalfPageScrollUp
+        | Action::HalfPageScrollDown
+        | Action::ScrollToBottom => 25,
+        Action::ToggleFocusFullscreen
+        | Action::ToggleFloatingPanes
+        | Action::TogglePaneFrames
+        | Action::TogglePaneEmbedOrFloating
+        | Action::ToggleActiveSyncTab => 30,
+        Action::NextSwapLayout | Action::PreviousSwapLayout => 32,
+        Action::ToggleGroupMarking | Action::TogglePaneInGroup => 34,
+        Action::PaneNameInput(..)
+        | Action::TabNameInput(..)
+        | Action::UndoRenamePane
+        | Action::UndoRenameTab => 35,
+        Action::Detach => 40,
+        Action::Write(..) | Action::WriteChars(..) => 45,
+        Action::SwitchToMode(mode) if *mode == base_mode => 60,
+        Action::SwitchToMode(..) => 55,
+        Action::Quit => 65,
+        _ => 50,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(keys: &[&str], label: &str) -> Entry {
+        Entry {
+            priority: 10,
+            pin: None,
+            hidden: false,
+            inapplicable: false,
+            nested: false,
+            keys: keys.iter().map(|s| s.to_string()).collect(),
+            label: label.to_string(),
+            actions: Vec::new(),
+            merged: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn display_width_counts_chars_not_bytes() {
+        assert_eq!(display_width("abc"), 3);
+        // Arrows are 3 bytes each but one column wide.
+        assert_eq!(display_width("← ↓ ↑ →"), 7);
+    }
+
+    #[test]
+    fn truncate_never_splits_a_multibyte_char() {
+        // The bug that used to panic: byte-slicing through a multibyte char.
+        let s = "→ Resize mode that is quite long";
+        for max in 0..=display_width(s) + 2 {
+            let out = truncate_to_width(s, max);
+            assert!(out.chars().count() <= max.max(1));
+            // Must always be valid UTF-8 / not panic, and round-trip as chars.
+            assert_eq!(out, out.chars().collect::<String>());
+        }
+    }
+
+    #[test]
+    fn truncate_adds_ellipsis_when_too_long() {
+        assert_eq!(truncate_to_width("abcdef", 4), "abc…");
+        assert_eq!(truncate_to_width("abc", 4), "abc");
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
 
-    // Leave room for the border (2) and one cell of padding on each side (2).
-    let max_inner = MAX_INNER_WIDTH.min(display_cols.saturating_sub(2 * MARGIN + 4));
-    let raw_label = entries
-        .iter()
-        .map(|e| display_width(&e.label))
-        .max()
-        .unwrap_or(1);
-    let label_col = raw_label.min(max_inner.saturating_sub(keys_col + 2)).max(1);
+    #[test]
+    fn pad_right_pads_to_width_and_never_truncates() {
+        assert_eq!(pad_right("ab", 4), "ab  ");
+        assert_eq!(pad_right("abcd", 2), "abcd");
+        assert_eq!(display_width(&pad_right("←", 3)), 3);
+    }
 
-    let inner = keys_col + 2 + label_col;
-    let pane_cols = (inner + 4)
-        .min(display_cols.saturating_sub(2 * MARGIN))
-        .max(8);
+    #[test]
+    fn wrap_breaks_at_spaces_and_hard_wraps_long_words() {
+        assert_eq!(wrap_to_width("new pane down", 8), vec!["new pane", "down"]);
+        assert_eq!(wrap_to_width("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(wrap_to_width("", 5), vec![""]);
+    }
 
-    let cap_rows = (display_rows.saturating_mul(max_height_pct) / 100).max(MIN_BOX_ROWS);
-    // Subtract the border (2) and the mode header (1) to get body capacity.
-    let body_avail = cap_rows.saturating_sub(3).max(1);
+    #[test]
+    fn category_names_the_priority_band() {
+        let base = InputMode::Normal;
+        assert_eq!(action_category(&[Action::CloseFocus], base), "Close");
+        assert_eq!(
+            action_category(&[Action::SwitchToMode(InputMode::Normal)], base),
+            "Back to base"
+        );
+    }
 
-    let (visible, overflow) = if entries.len() <= body_avail {
-        (entries.len(), 0)
-    } else {
-        // One body line goes to the "+N more" indicator.
-        let visible = body_avail.saturating_sub(1).max(1);
-        (visible, entries.len() - visible)
-    };
+    #[test]
+    fn nav_clamps_to_the_list() {
+        assert_eq!(nav_target(3, Nav::Up(10), 20), 0);
+        assert_eq!(nav_target(3, Nav::Down(10), 20), 13);
+        assert_eq!(nav_target(15, Nav::Down(10), 20), 19);
+        assert_eq!(nav_target(7, Nav::Top, 20), 0);
+        assert_eq!(nav_target(7, Nav::Bottom, 20), 19);
+        assert_eq!(nav_target(0, Nav::Bottom, 0), 0);
+    }
 
-    let content_rows = 1 + visible + usize::from(overflow > 0);
-    let pane_rows = (content_rows + 2).max(MIN_BOX_ROWS);
+    #[test]
+    fn line_editor_inserts_and_deletes_at_the_cursor() {
+        let mut ed = LineEditor::new("new pane".to_string());
+        ed.home();
+        ed.insert('→');
+        assert_eq!(ed.text(), "→new pane");
+        ed.right();
+        assert!(ed.delete());
+        assert_eq!(ed.text(), "→nw pane");
+        assert!(ed.backspace());
+        assert_eq!(ed.text(), "→w pane");
+        ed.end();
+        assert!(!ed.delete());
+    }
 
-    Layout {
-        pane_cols,
-        pane_rows,
-        keys_col,
-        label_col,
-        visible,
-        overflow,
+    #[test]
+    fn line_editor_moves_and_deletes_by_word() {
+        let mut ed = LineEditor::new("go to  tab".to_string());
+        assert!(ed.word_left());
+        assert_eq!(ed.split_at_cursor().1, "t");
+        ed.word_left();
+        assert!(ed.delete_word_back());
+        assert_eq!(ed.text(), "to  tab");
+        ed.word_right();
+        assert_eq!(
+            ed.split_at_cursor(),
+            ("to".into(), " ".into(), " tab".into())
+        );
+        ed.end();
+        ed.delete_word_back();
+        assert_eq!(ed.text(), "to  ");
+    }
+
+    #[test]
+    fn history_recalls_older_then_restores_the_draft() {
+        let mut h = QueryHistory::default();
+        h.record("pane");
+        h.record("tab");
+        h.record("pane");
+        assert_eq!(h.entries, vec!["tab", "pane"]);
+        assert_eq!(h.older("dra").as_deref(), Some("pane"));
+        assert_eq!(h.older("").as_deref(), Some("tab"));
+        assert_eq!(h.older(""), None);
+        assert_eq!(h.newer().as_deref(), Some("pane"));
+        assert_eq!(h.newer().as_deref(), Some("dra"));
+        assert_eq!(h.newer(), None);
+    }
+
+    #[test]
+    fn history_parse_skips_blanks_and_keeps_the_newest() {
+        assert_eq!(parse_history("a\n\n b \n"), vec!["a", "b"]);
+        let long: String = (0..HISTORY_MAX + 5).map(|i| format!("q{}\n", i)).collect();
+        let parsed = parse_history(&long);
+        assert_eq!(parsed.len(), HISTORY_MAX);
+        assert_eq!(parsed[0], "q5");
+    }
+
+    fn key(c: char) -> KeyWithModifier {
+        KeyWithModifier::new(BareKey::Char(c))
+    }
+
+    #[test]
+    fn bytes_use_caret_notation_for_control_chars() {
+        assert_eq!(format_bytes(&[0x0c]), "^L");
+        assert_eq!(format_bytes(b"\x1b[A"), "Esc[A");
+        assert_eq!(format_bytes(b"ls\r"), "ls^M");
+    }
+
+    #[test]
+    fn noise_is_hidden_unless_requested() {
+        let binds = vec![
+            (key('l'), vec![Action::Write(None, vec![0x0c], false)]),
+            (key('g'), vec![Action::WriteChars("git status".to_string())]),
+            (key('x'), vec![Action::CloseFocus]),
+        ];
+        let none = std::collections::HashSet::new();
+        let base = InputMode::Normal;
+        let noisy = Grouping {
+            show_noise: true,
+            ..Grouping::default()
+        };
+        let quiet = group_bindings(&binds, InputMode::Pane, base, &none, &Grouping::default());
+        assert_eq!(quiet.len(), 1);
+        let labels: Vec<String> = group_bindings(&binds, InputMode::Pane, base, &none, &noisy)
+            .into_iter()
+            .map(|e| e.label)
+            .collect();
+        assert!(labels.contains(&"Send ^L".to_string()));
+        assert!(labels.contains(&"Type 'git status'".to_string()));
+    }
+
+    #[test]
+    fn counts_round_trip_and_skip_garbage() {
+        let parsed = parse_counts("Pane\t3\nbroken\nTab\tx\nResize\t12\n");
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["Pane"], 3);
+        assert_eq!(parse_counts(&format_counts(&parsed)), parsed);
+    }
+
+    #[test]
+    fn usage_round_trips_and_rejects_other_versions() {
+        let mut stats = UsageStats {
+            since: 100,
+            ..UsageStats::default()
+        };
+        stats.transitions.insert(
+            ("Normal".into(), "Pane".into()),
+            TransitionStats {
+                count: 7,
+                last_used: 250,
+            },
+        );
+        stats.actions.insert(
+            ("Pane".into(), "NewPane".into()),
+            TransitionStats {
+                count: 3,
+                last_used: 240,
+            },
+        );
+        assert_eq!(parse_usage(&format_usage(&stats)), Some(stats));
+        assert_eq!(parse_usage("zj-which-key-usage 0\nsince\t1\n"), None);
+        assert_eq!(parse_usage(""), None);
+    }
+
+    #[test]
+    fn usage_sort_puts_used_switches_first_and_keeps_priority_ties() {
+        let mut entries = vec![
+            Entry {
+                actions: vec![Action::NewPane(None, None, false)],
+                ..entry(&["n"], "New pane")
+            },
+            Entry {
+                actions: vec![Action::SwitchToMode(InputMode::Pane)],
+                ..entry(&["p"], "Pane mode")
+            },
+            Entry {
+                actions: vec![Action::SwitchToMode(InputMode::Tab)],
+                ..entry(&["t"], "Tab mode")
+            },
+        ];
+        let usage = parse_usage_counts("Tmux>Tab=9,Tmux>Pane=2,Tmux>Normal=99,bad");
+        assert_eq!(usage.transitions.len(), 3);
+        sort_by_usage(&mut entries, InputMode::Tmux, InputMode::Normal, &usage);
+        let labels: Vec<&str> = entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["Tab mode", "Pane mode", "New pane"]);
+        assert_eq!(
+            parse_usage_counts(&format_usage_counts(&usage)).transitions,
+            usage.transitions
+        );
+    }
+
+    #[test]
+    fn usage_sort_counts_inferred_actions() {
+        let back = |action: Action| vec![action, Action::SwitchToMode(InputMode::Normal)];
+        let mut entries = vec![
+            Entry {
+                actions: back(Action::NewPane(None, None, false)),
+                ..entry(&["n"], "New pane")
+            },
+            Entry {
+                actions: back(Action::CloseFocus),
+                ..entry(&["x"], "Close pane")
+            },
+            Entry {
+                actions: vec![Action::MoveFocus(Direction::Left)],
+                merged: vec![
+                    vec![Action::MoveFocus(Direction::Down)],
+                    vec![Action::MoveFocus(Direction::Up)],
+                ],
+                ..entry(&["h", "j", "k"], "Focus")
+            },
+        ];
+        let usage = parse_usage_counts("Pane:CloseFocus=4,Pane:MoveFocus=2,Tab:NewPane=9");
+        sort_by_usage(&mut entries, InputMode::Pane, InputMode::Normal, &usage);
+        let labels: Vec<&str> = entries.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(labels, vec!["Close pane", "Focus", "New pane"]);
+        assert_eq!(
+            parse_usage_counts(&format_usage_counts(&usage)).actions,
+            usage.actions
+        );
+    }
+
+    #[test]
+    fn actions_are_inferred_from_tab_and_pane_changes() {
+        let tabs = TabsSeen {
+            count: 3,
+            active: 0,
+            fullscreen: false,
+            floating: false,
+            floating_panes: 2,
+            sync: false,
+        };
+        let new_tab = TabsSeen {
+            count: 4,
+            active: 3,
+            ..tabs
+        };
+        assert_eq!(tab_actions(&tabs, &new_tab), vec!["NewTab"]);
+        let next = TabsSeen { active: 1, ..tabs };
+        assert_eq!(tab_actions(&tabs, &next)[0], "GoToNextTab");
+        let wrapped = TabsSeen { active: 2, ..tabs };
+        assert_eq!(tab_actions(&tabs, &wrapped)[0], "GoToPreviousTab");
+        let fullscreen = TabsSeen {
+            fullscreen: true,
+            ..tabs
+        };
+        assert_eq!(
+            tab_actions(&tabs, &fullscreen),
+            vec!["ToggleFocusFullscreen"]
+        );
+        assert!(tab_actions(&tabs, &tabs).is_empty());
+        let shown = TabsSeen {
+            floating: true,
+            ..tabs
+        };
+        assert_eq!(tab_actions(&tabs, &shown), vec!["ToggleFloatingPanes"]);
+        // The popup opening shows the floating layer without a toggle.
+        let popup = TabsSeen {
+            floating: true,
+            floating_panes: 0,
+            ..tabs
+        };
+        let before = TabsSeen {
+            floating_panes: 0,
+            ..tabs
+        };
+        assert!(tab_actions(&before, &popup).is_empty());
+
+        let panes = PanesSeen {
+            tab: 0,
+            panes: 2,
+            focused: Some((false, false, 1)),
+        };
+        // A new pane takes focus too; that's one NewPane, not a focus move.
+        let new_pane = PanesSeen {
+            panes: 3,
+            focused: Some((false, false, 5)),
+            ..panes
+        };
+        assert_eq!(pane_actions(&panes, &new_pane), vec!["NewPane"]);
+        let moved = PanesSeen {
+            focused: Some((false, false, 2)),
+            ..panes
+        };
+        assert_eq!(pane_actions(&panes, &moved)[0], "MoveFocus");
+        // Focus jumping to the floating layer, or another tab's panes, isn't
+        // a pane action.
+        let floating = PanesSeen {
+            focused: Some((true, false, 7)),
+            ..panes
+        };
+        assert!(pane_actions(&panes, &floating).is_empty());
+        let other_tab = PanesSeen { tab: 1, ..new_pane };
+        assert!(pane_actions(&panes, &other_tab).is_empty());
+    }
+
+    #[test]
+    fn switch_target_is_the_last_mode_switch() {
+        let actions = vec![
+            Action::NewPane(None, None, false),
+            Action::SwitchToMode(InputMode::Normal),
+        ];
+        assert_eq!(switch_target(&actions), Some(InputMode::Normal));
+        assert_eq!(switch_target(&[Action::CloseFocus]), None);
+    }
+
+    #[test]
+    fn learned_delay_grows_past_the_threshold_and_caps() {
+        assert_eq!(learned_delay(0.5, 10, 50), 0.5);
+        assert_eq!(learned_delay(0.5, 50, 50), 0.5);
+        assert_eq!(learned_delay(0.5, 100, 50), 1.0);
+        assert_eq!(learned_delay(0.5, 10_000, 50), 0.5 * LEARN_MAX_DELAY_FACTOR);
+    }
+
+    #[test]
+    fn priority_overrides_and_pins_reorder_entries() {
+        let binds = vec![
+            (key('x'), vec![Action::CloseFocus]),
+            (key('n'), vec![Action::NewPane(None, None, false)]),
+            (key('f'), vec![Action::ToggleFocusFullscreen]),
+            (key('w'), vec![Action::ToggleFloatingPanes]),
+        ];
+        let grouping = Grouping {
+            priorities: parse_priorities("close pane=90, NewPane = 80, junk, Bad=999"),
+            pins: parse_list("w, Fullscreen"),
+            ..Grouping::default()
+        };
+        assert_eq!(grouping.priorities.len(), 2);
+        let none = std::collections::HashSet::new();
+        let labels: Vec<String> =
+            group_bindings(&binds, InputMode::Pane, InputMode::Normal, &none, &grouping)
+                .into_iter()
+                .map(|e| e.label)
+                .collect();
+        assert_eq!(
+            labels,
+            vec!["Toggle floating", "Fullscreen", "New pane", "Close pane"]
+        );
+    }
+
+    #[test]
+    fn hidden_bindings_are_dropped_or_kept_apart() {
+        let binds = vec![
+            (key('q'), vec![Action::Quit]),
+            (key('d'), vec![Action::Detach]),
+            (key('x'), vec![Action::Detach]),
+        ];
+        let mut grouping = Grouping {
+            hide_actions: parse_scoped("Session:Quit"),
+            hide_keys: parse_scoped("x"),
+            ..Grouping::default()
+        };
+        let none = std::collections::HashSet::new();
+        let base = InputMode::Normal;
+        let session = group_bindings(&binds, InputMode::Session, base, &none, &grouping);
+        assert_eq!(session.len(), 1);
+        assert_eq!(session[0].keys, vec!["d"]);
+        // The mode prefix limits the action hide to Session mode.
+        let tmux = group_bindings(&binds, InputMode::Tmux, base, &none, &grouping);
+        assert_eq!(tmux.len(), 2);
+
+        grouping.keep_hidden = true;
+        let kept = group_bindings(&binds, InputMode::Session, base, &none, &grouping);
+        let hidden: Vec<&str> = kept
+            .iter()
+            .filter(|e| e.hidden)
+            .map(|e| e.label.as_str())
+            .collect();
+        assert_eq!(kept.len(), 3);
+        assert_eq!(hidden, vec!["Detach", "Quit zellij"]);
+    }
+
+    #[test]
+    fn scoped_items_only_take_alphabetic_mode_prefixes() {
+        let items = parse_scoped("Session:Quit, Ctrl+:, Detach, enter_search:Esc");
+        assert_eq!(items[0].mode.as_deref(), Some("Session"));
+        assert_eq!(items[0].name, "Quit");
+        assert_eq!(items[1].mode, None);
+        assert_eq!(items[1].name, "Ctrl+:");
+        assert!(items[0].applies_to(InputMode::Session));
+        assert!(!items[0].applies_to(InputMode::Pane));
+        // The `desc_<mode>` spelling works too.
+        assert!(items[3].applies_to(InputMode::EnterSearch));
+        assert!(!items[3].applies_to(InputMode::Search));
+        assert_eq!(
+            format_scoped(&items),
+            "Session:Quit,Ctrl+:,Detach,enter_search:Esc"
+        );
+    }
+
+    #[test]
+    fn mode_keys_are_snake_case() {
+        assert_eq!(mode_key(InputMode::Pane), "pane");
+        assert_eq!(mode_key(InputMode::EnterSearch), "enter_search");
+        assert_eq!(mode_key(InputMode::RenamePane), "rename_pane");
+        // The browser's mode column tells the two search modes apart.
+        assert_ne!(
+            mode_title(InputMode::EnterSearch),
+            mode_title(InputMode::Search)
+        );
+    }
+
+    #[test]
+    fn switch_labels_use_the_mode_heading() {
+        let base = InputMode::Normal;
+        let label = |mode| format_action(&[Action::SwitchToMode(mode)], base);
+        assert_eq!(label(InputMode::Pane), "Pane mode");
+        assert_eq!(label(InputMode::EnterSearch), "Search (type query)");
+        assert_eq!(label(InputMode::Normal), "Back to normal");
+    }
+
+    #[test]
+    fn layout_reserves_the_header_rows() {
+        let entries = vec![entry(&["n"], "New pane")];
+        let one = compute_layout(&entries, &[], 1, 120, 40, 40);
+        let two = compute_layout(&entries, &[], 2, 120, 40, 40);
+        assert_eq!(two.pane_rows, one.pane_rows + 1);
+    }
+
+    #[test]
+    fn shared_mode_exits_are_labelled_as_the_switch() {
+        let normal = |actions: Vec<Action>| {
+            [actions, vec![Action::SwitchToMode(InputMode::Normal)]].concat()
+        };
+        let mut state = State::default();
+        state.mode_info.keybinds = vec![(
+            InputMode::Pane,
+            vec![
+                (key('n'), normal(vec![Action::NewPane(None, None, false)])),
+                (key('x'), normal(vec![Action::CloseFocus])),
+                (key('r'), vec![Action::SwitchToMode(InputMode::RenamePane)]),
+            ],
+        )];
+        let exit = state
+            .transition_entry(InputMode::Pane, InputMode::Normal)
+            .unwrap();
+        assert!(exit.label.starts_with("Exit to Normal mode via "));
+        assert_eq!(exit.keys.len(), 2);
+        // A switch made by one key is just that key's entry.
+        let rename = state
+            .transition_entry(InputMode::Pane, InputMode::RenamePane)
+            .unwrap();
+        assert!(!rename.label.starts_with("Exit"));
+    }
+
+    #[test]
+    fn short_panel_keeps_to_its_rows() {
+        let pane = vec![
+            (key('n'), vec![Action::NewPane(None, None, false)]),
+            (key('x'), vec![Action::CloseFocus]),
+            (key('f'), vec![Action::ToggleFocusFullscreen]),
+            (key('z'), vec![Action::TogglePaneFrames]),
+        ];
+        let normal = vec![bind(BareKey::Char('q'), true, vec![Action::Quit])];
+        let mut state = State {
+            role: Role::Panel,
+            ..State::default()
+        };
+        state.mode_info.mode = InputMode::Pane;
+        state.mode_info.keybinds = vec![
+            (InputMode::Normal, normal.clone()),
+            (InputMode::Pane, [pane, normal].concat()),
+        ];
+        for rows in 1..=3 {
+            assert_eq!(state.panel_lines(rows, 40).0.len(), rows);
+        }
+        state.globals = Globals::Section;
+        assert_eq!(state.panel_lines(3, 40).0.len(), 3);
+        state.popup_layout = PopupLayout::Strip;
+        assert_eq!(state.panel_lines(2, 40).0.len(), 2);
+    }
+
+    #[test]
+    fn browser_prompt_has_ctrl_alternates_to_shadowed_alt_keys() {
+        let mut state = State {
+            role: Role::Browser,
+            ..State::default()
+        };
+        state.history.record("pane");
+        let ctrl = |k: BareKey| KeyWithModifier::new(k).with_ctrl_modifier();
+        assert!(state.handle_browser_key(ctrl(BareKey::Up)));
+        assert_eq!(state.query.text(), "pane");
+        assert!(state.handle_browser_key(ctrl(BareKey::Down)));
+        assert_eq!(state.query.text(), "");
+        for c in "new pane".chars() {
+            state.handle_browser_key(KeyWithModifier::new(BareKey::Char(c)));
+        }
+        assert!(state.handle_browser_key(ctrl(BareKey::Left)));
+        assert_eq!(state.query.split_at_cursor().0, "new ");
+        assert!(state.handle_browser_key(ctrl(BareKey::Right)));
+        assert_eq!(state.query.split_at_cursor().0, "new pane");
+    }
+
+    #[test]
+    fn vim_normal_mode_keys() {
+        let mut state = State {
+            role: Role::Browser,
+            browser_keys: BrowserKeys::Vim,
+            ..State::default()
+        };
+        state.mode_info.keybinds = vec![(
+            InputMode::Pane,
+            vec![
+                (key('n'), vec![Action::NewPane(None, None, false)]),
+                (key('x'), vec![Action::CloseFocus]),
+                (key('f'), vec![Action::ToggleFocusFullscreen]),
+                (key('z'), vec![Action::TogglePaneFrames]),
+            ],
+        )];
+        let press = |state: &mut State, k: BareKey| {
+            state.handle_browser_key(KeyWithModifier::new(k));
+        };
+        // Esc leaves insert mode without closing.
+        press(&mut state, BareKey::Esc);
+        assert!(state.vim_normal && !state.closing);
+        press(&mut state, BareKey::End);
+        assert_eq!(state.selected, 3);
+        // `g` then `j` is just `j`, dropping the pending `g`; `gg` goes to
+        // the top.
+        press(&mut state, BareKey::Char('k'));
+        press(&mut state, BareKey::Char('g'));
+        press(&mut state, BareKey::Char('j'));
+        press(&mut state, BareKey::Char('g'));
+        assert_eq!(state.selected, 3);
+        press(&mut state, BareKey::Char('g'));
+        assert_eq!(state.selected, 0);
+        state.handle_browser_key(KeyWithModifier::new(BareKey::Char('n')).with_ctrl_modifier());
+        assert_eq!(state.selected, 1);
+        // `/` goes back to typing; Esc, Esc closes.
+        press(&mut state, BareKey::Char('/'));
+        assert!(!state.vim_normal);
+        press(&mut state, BareKey::Char('x'));
+        assert_eq!(state.query.text(), "x");
+        press(&mut state, BareKey::Esc);
+        assert!(state.vim_normal && !state.closing);
+        press(&mut state, BareKey::Esc);
+        assert!(state.closing);
+    }
+
+    fn bind(key: BareKey, ctrl: bool, actions: Vec<Action>) -> (KeyWithModifier, Vec<Action>) {
+        let key = KeyWithModifier::new(key);
+        (if ctrl { key.with_ctrl_modifier() } else { key }, actions)
+    }
+
+    #[test]
+    fn rename_modes_get_confirm_and_cancel_from_their_bindings() {
+        // Zellij's default `shared_except "locked"` bindings, which also
+        // reach `renametab`.
+        let shared = [
+            bind(
+                BareKey::Char('g'),
+                true,
+                vec![Action::SwitchToMode(InputMode::Locked)],
+            ),
+            bind(BareKey::Char('q'), true, vec![Action::Quit]),
+            (
+                KeyWithModifier::new(BareKey::Char('n')).with_alt_modifier(),
+                vec![Action::NewPane(None, None, false)],
+            ),
+            bind(
+                BareKey::Char('p'),
+                true,
+                vec![Action::SwitchToMode(InputMode::Pane)],
+            ),
+        ];
+        let exclude = shared
+            .iter()
+            .map(|(key, actions)| binding_signature(key, actions, InputMode::Normal))
+            .collect();
+        // Zellij's default `renametab` bindings.
+        let mut binds = vec![
+            bind(
+                BareKey::Char('c'),
+                true,
+                vec![Action::SwitchToMode(InputMode::Normal)],
+            ),
+            bind(
+                BareKey::Esc,
+                false,
+                vec![Action::UndoRenameTab, Action::SwitchToMode(InputMode::Tab)],
+            ),
+        ];
+        binds.extend(shared.iter().cloned());
+        let rows = |entries: Vec<Entry>| -> Vec<(String, String)> {
+            entries
+                .iter()
+                .map(|e| (e.keys_str(), e.label.clone()))
+                .collect()
+        };
+        let entries = text_entry_entries(
+            &binds,
+            InputMode::RenameTab,
+            InputMode::Normal,
+            &exclude,
+            &Grouping::default(),
+        );
+        assert_eq!(
+            rows(entries),
+            vec![
+                ("↵ Ctrl+c".to_string(), "Confirm".to_string()),
+                ("Esc".to_string(), "Cancel (undo rename)".to_string()),
+            ]
+        );
+
+        // With `globals "show"` the shared bindings are listed on their own;
+        // switching to another mode doesn't confirm the rename.
+        let entries = text_entry_entries(
+            &binds,
+            InputMode::RenameTab,
+            InputMode::Normal,
+            &std::collections::HashSet::new(),
+            &Grouping::default(),
+        );
+        let rows = rows(entries);
+        assert_eq!(rows[0], ("↵ Ctrl+c".to_string(), "Confirm".to_string()));
+        assert_eq!(rows.len(), 6);
+        assert!(rows[2..].iter().all(|(_, label)| label != "Confirm"));
+    }
+
+    #[test]
+    fn search_entry_confirms_on_the_search_switch() {
+        // Zellij's default `entersearch` bindings.
+        let binds = vec![
+            bind(
+                BareKey::Esc,
+                false,
+                vec![Action::SwitchToMode(InputMode::Scroll)],
+            ),
+            bind(
+                BareKey::Enter,
+                false,
+                vec![Action::SwitchToMode(InputMode::Search)],
+            ),
+        ];
+        let entries = text_entry_entries(
+            &binds,
+            InputMode::EnterSearch,
+            InputMode::Normal,
+            &std::collections::HashSet::new(),
+            &Grouping::default(),
+        );
+        assert_eq!(entries[0].label, "Confirm");
+        assert_eq!(entries[0].keys, vec!["↵"]);
+        assert_eq!(entries[1].label, "Cancel");
+        assert_eq!(entries[1].keys, vec!["Esc"]);
+        assert_eq!(text_entry_prompt(InputMode::Pane), None);
+    }
+
+    fn context<'a>(tab: &'a TabInfo, panes: &'a [PaneInfo]) -> TabContext<'a> {
+        TabContext {
+            tab,
+            panes,
+            tab_count: 1,
+            own_id: 99,
+        }
+    }
+
+    #[test]
+    fn summary_lists_only_active_flags() {
+        let mut tab = TabInfo {
+            name: "editor".to_string(),
+            selectable_tiled_panes_count: 2,
+            selectable_floating_panes_count: 1,
+            ..TabInfo::default()
+        };
+        assert_eq!(context(&tab, &[]).summary(), "editor · 3 panes");
+        tab.is_sync_panes_active = true;
+        tab.are_floating_panes_visible = true;
+        assert_eq!(
+            context(&tab, &[]).summary(),
+            "editor · 3 panes · sync · floating"
+        );
+    }
+
+    #[test]
+    fn focused_pane_follows_the_visible_layer() {
+        let mut tab = TabInfo::default();
+        let tiled = PaneInfo {
+            id: 1,
+            ..tiled_pane(0, 0, 40, 20, true)
+        };
+        let floating = PaneInfo {
+            id: 2,
+            is_floating: true,
+            ..tiled_pane(10, 5, 20, 10, true)
+        };
+        let panes = [tiled, floating];
+        assert_eq!(context(&tab, &panes).focused_pane().map(|p| p.id), Some(1));
+        tab.are_floating_panes_visible = true;
+        assert_eq!(context(&tab, &panes).focused_pane().map(|p| p.id), Some(2));
+        // With the popup as the only floating pane, focus is the tiled one.
+        let popup = PaneInfo {
+            id: 99,
+            is_plugin: true,
+            ..panes[1].clone()
+        };
+        let panes = [panes[0].clone(), popup];
+        assert_eq!(context(&tab, &panes).focused_pane().map(|p| p.id), Some(1));
+    }
+
+    #[test]
+    fn focused_pane_anchor_survives_the_popup_showing() {
+        let tiled = PaneInfo {
+            id: 1,
+            ..tiled_pane(0, 0, 50, 40, true)
+        };
+        let popup = PaneInfo {
+            id: 99,
+            is_plugin: true,
+            is_floating: true,
+            ..tiled_pane(30, 30, 18, 8, true)
+        };
+        let mut state = State {
+            anchor: Anchor::FocusedPane,
+            own_id: 99,
+            display_cols: 100,
+            display_rows: 40,
+            tabs: vec![TabInfo {
+                active: true,
+                are_floating_panes_visible: true,
+                ..TabInfo::default()
+            }],
+            ..State::default()
+        };
+        state.pane_manifest.panes.insert(0, vec![tiled, popup]);
+        assert_eq!(state.anchor_area(), Some((0, 0, 50, 40)));
+    }
+
+    #[test]
+    fn placement_takes_the_configured_corner_when_clear() {
+        let area = (0, 0, 100, 40);
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomRight, &[], true, 0),
+            (69, 29)
+        );
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomLeft, &[], true, 0),
+            (1, 29)
+        );
+        // Inside a pane's area.
+        assert_eq!(
+            place_box(
+                (50, 10, 50, 30),
+                (30, 10),
+                Position::BottomLeft,
+                &[],
+                true,
+                0
+            ),
+            (51, 29)
+        );
+    }
+
+    #[test]
+    fn placement_sits_between_the_bars() {
+        let bar = |y: usize, rows: usize| PaneInfo {
+            is_plugin: true,
+            pane_y: y,
+            pane_columns: 100,
+            pane_rows: rows,
+            ..PaneInfo::default()
+        };
+        let panes = [bar(0, 1), bar(38, 2), tiled_pane(0, 1, 100, 37, true)];
+        assert_eq!(
+            place_box(
+                (0, 0, 100, 40),
+                (30, 10),
+                Position::BottomRight,
+                &panes,
+                true,
+                99
+            ),
+            (69, 27)
+        );
+    }
+
+    #[test]
+    fn placement_falls_back_around_floating_panes() {
+        let floating = |x: usize, y: usize| PaneInfo {
+            is_floating: true,
+            pane_x: x,
+            pane_y: y,
+            pane_columns: 40,
+            pane_rows: 15,
+            ..PaneInfo::default()
+        };
+        let area = (0, 0, 100, 40);
+        // Bottom right is taken: bottom left is next.
+        let panes = [floating(60, 25)];
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomRight, &panes, true, 0),
+            (1, 29)
+        );
+        // Hidden floating panes aren't in the way.
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomRight, &panes, false, 0),
+            (69, 29)
+        );
+        // Both bottom corners taken: top right.
+        let panes = [floating(60, 25), floating(0, 25)];
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomRight, &panes, true, 0),
+            (69, 1)
+        );
+        // Our own pane doesn't count.
+        let own = PaneInfo {
+            id: 7,
+            is_plugin: true,
+            ..floating(60, 25)
+        };
+        assert_eq!(
+            place_box(area, (30, 10), Position::BottomRight, &[own], true, 7),
+            (69, 29)
+        );
+    }
+
+    #[test]
+    fn toggle_labels_show_the_current_and_next_state() {
+        let tab = TabInfo {
+            is_fullscreen_active: true,
+            ..TabInfo::default()
+        };
+        let ctx = context(&tab, &[]);
+        assert_eq!(
+            ctx.toggle_label(&[Action::ToggleFocusFullscreen])
+                .as_deref(),
+            Some("Fullscreen: on → off")
+        );
+        assert_eq!(
+            ctx.toggle_label(&[Action::ToggleActiveSyncTab]).as_deref(),
+            Some("Sync: off → on")
+        );
+        assert_eq!(ctx.toggle_label(&[Action::CloseFocus]), None);
+        // Frames are unknown until the panes arrive.
+        assert_eq!(ctx.toggle_label(&[Action::TogglePaneFrames]), None);
+    }
+
+    #[test]
+    fn frames_are_read_from_the_focused_tiled_pane() {
+        let tab = TabInfo::default();
+        let framed = PaneInfo {
+            is_focused: true,
+            pane_x: 0,
+            pane_content_x: 1,
+            ..PaneInfo::default()
+        };
+        let floating = PaneInfo {
+            is_floating: true,
+            ..framed.clone()
+        };
+        assert_eq!(
+            context(&tab, std::slice::from_ref(&framed)).frames_visible(),
+            Some(true)
+        );
+        let bare = PaneInfo {
+            pane_content_x: 0,
+            ..framed
+        };
+        assert_eq!(
+            context(&tab, &[floating, bare]).frames_visible(),
+            Some(false)
+        );
+    }
+
+    /// A selectable tiled pane at `x, y` of `cols` by `rows`.
+    fn tiled_pane(x: usize, y: usize, cols: usize, rows: usize, focused: bool) -> PaneInfo {
+        PaneInfo {
+            is_selectable: true,
+            is_focused: focused,
+            pane_x: x,
+            pane_y: y,
+            pane_columns: cols,
+            pane_rows: rows,
+            ..PaneInfo::default()
+        }
+    }
+
+    #[test]
+    fn on_demand_cheat_sheet_closes_on_the_next_thing_you_do() {
+        let mut state = State {
+            role: Role::Popup,
+            base_summary: true,
+            own_id: 99,
+            tabs: vec![TabInfo {
+                active: true,
+                ..TabInfo::default()
+            }],
+            ..State::default()
+        };
+        let popup = |focused: bool| PaneInfo {
+            id: 99,
+            is_plugin: true,
+            is_floating: true,
+            ..tiled_pane(50, 14, 28, 6, focused)
+        };
+        let show = |state: &mut State, panes: Vec<PaneInfo>| {
+            state.pane_manifest.panes = HashMap::from([(0, panes)]);
+            state.cheat_sheet_used()
+        };
+        let shell = PaneInfo {
+            id: 1,
+            ..tiled_pane(0, 0, 80, 20, true)
+        };
+        // Opening (and handing focus back) is not a use.
+        assert!(!show(&mut state, vec![shell.clone()]));
+        assert!(!show(&mut state, vec![shell.clone(), popup(true)]));
+        assert!(!show(&mut state, vec![shell.clone(), popup(false)]));
+        // Pressing its key again focuses it.
+        assert!(show(&mut state, vec![shell.clone(), popup(true)]));
+        // A base-mode key that opens a pane.
+        let other = PaneInfo {
+            id: 2,
+            ..tiled_pane(40, 0, 40, 20, true)
+        };
+        assert!(!show(&mut state, vec![shell.clone(), popup(false)]));
+        assert!(show(&mut state, vec![shell.clone(), other, popup(false)]));
+        // Not for the controller's popup.
+        state.controller_id = Some(1);
+        assert!(!show(&mut state, vec![shell, popup(true)]));
     }
-}
 
-/// Visible width of a string. Our key/label glyphs are all single-width, so a
-/// char count is exact here and avoids a unicode-width dependency.
-fn display_width(s: &str) -> usize {
-    s.chars().count()
-}
+    #[test]
+    fn tab_actions_need_another_tab() {
+        let tab = TabInfo::default();
+        let mut ctx = context(&tab, &[]);
+        assert!(!ctx.applies(&[Action::CloseTab]));
+        assert!(!ctx.applies(&[Action::GoToNextTab]));
+        assert!(!ctx.applies(&[Action::GoToTab(2)]));
+        assert!(ctx.applies(&[Action::GoToTab(1)]));
+        ctx.tab_count = 2;
+        assert!(ctx.applies(&[Action::CloseTab, Action::SwitchToMode(InputMode::Normal)]));
+        assert!(ctx.applies(&[Action::GoToTab(2)]));
+    }
 
-/// Truncate to `max` columns with an ellipsis, never splitting a char.
-fn truncate_to_width(s: &str, max: usize) -> String {
-    if max == 0 {
-        return String::new();
+    #[test]
+    fn swap_layouts_need_an_active_swap_layout() {
+        let mut tab = TabInfo::default();
+        assert!(!context(&tab, &[]).applies(&[Action::PreviousSwapLayout]));
+        tab.active_swap_layout_name = Some("vertical".to_string());
+        assert!(context(&tab, &[]).applies(&[Action::NextSwapLayout]));
     }
-    if display_width(s) <= max {
-        return s.to_string();
+
+    #[test]
+    fn focus_moves_need_a_pane_in_that_direction() {
+        let tab = TabInfo {
+            selectable_tiled_panes_count: 3,
+            ..TabInfo::default()
+        };
+        // A left pane beside a right column split top/bottom; focus top right.
+        let panes = [
+            tiled_pane(0, 0, 40, 20, false),
+            tiled_pane(40, 0, 40, 10, true),
+            tiled_pane(40, 10, 40, 10, false),
+        ];
+        let ctx = context(&tab, &panes);
+        assert!(ctx.applies(&[Action::MoveFocus(Direction::Left)]));
+        assert!(ctx.applies(&[Action::MoveFocus(Direction::Down)]));
+        assert!(!ctx.applies(&[Action::MoveFocus(Direction::Right)]));
+        assert!(!ctx.applies(&[Action::MovePane(Some(Direction::Up))]));
+        assert!(!ctx.applies(&[Action::MoveFocusOrTab(Direction::Right)]));
+        assert!(ctx.applies(&[Action::FocusNextPane]));
+        // Another tab gives MoveFocusOrTab somewhere to go.
+        let ctx = TabContext {
+            tab_count: 2,
+            ..ctx
+        };
+        assert!(ctx.applies(&[Action::MoveFocusOrTab(Direction::Right)]));
+        assert!(!ctx.applies(&[Action::MoveFocusOrTab(Direction::Up)]));
     }
-    let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
-    out.push('…');
-    out
-}
 
-fn pad_right(s: &str, width: usize) -> String {
-    let w = display_width(s);
-    let mut out = s.to_string();
-    if w < width {
-        out.extend(std::iter::repeat_n(' ', width - w));
+    #[test]
+    fn unknown_focus_keeps_everything_applicable() {
+        let tab = TabInfo {
+            are_floating_panes_visible: true,
+            ..TabInfo::default()
+        };
+        let floating = PaneInfo {
+            id: 2,
+            is_floating: true,
+            ..tiled_pane(10, 5, 20, 10, true)
+        };
+        let panes = [tiled_pane(0, 0, 80, 20, true), floating];
+        assert!(context(&tab, &panes).applies(&[Action::MoveFocus(Direction::Left)]));
+        let tab = TabInfo::default();
+        assert!(context(&tab, &[]).applies(&[Action::MoveFocus(Direction::Left)]));
+        assert!(!context(&tab, &panes[..1]).applies(&[Action::MoveFocus(Direction::Left)]));
     }
-    out
-}
 
-/// Stable identity for a `key -> action` binding, used to match the same
-/// binding across modes (e.g. to detect globals present in the base mode).
-fn binding_signature(key: &KeyWithModifier, actions: &[Action], base_mode: InputMode) -> String {
-    format!("{}\t{}", format_key(key), format_action(actions, base_mode))
-}
+    #[test]
+    fn our_own_popup_does_not_count_as_floating_focus() {
+        // Showing the popup makes the floating layer visible, with the popup
+        // as its only (focused) pane; focus is still on the tiled pane.
+        let tab = TabInfo {
+            are_floating_panes_visible: true,
+            ..TabInfo::default()
+        };
+        let popup = PaneInfo {
+            id: 99,
+            is_plugin: true,
+            is_floating: true,
+            is_selectable: false,
+            ..tiled_pane(50, 14, 28, 6, true)
+        };
+        let panes = [tiled_pane(0, 0, 80, 20, true), popup];
+        let ctx = context(&tab, &panes);
+        assert!(!ctx.applies(&[Action::MoveFocus(Direction::Left)]));
+        assert!(!ctx.applies(&[Action::MovePane(Some(Direction::Up))]));
+        assert!(!ctx.applies(&[Action::MoveFocusOrTab(Direction::Right)]));
+    }
 
-fn is_noise(actions: &[Action]) -> bool {
-    match actions.first() {
-        None => true,
-        // Raw "type this into the terminal" bindings carry no useful hint.
-        Some(Action::Write(..)) | Some(Action::WriteChars(..)) => true,
-        _ => false,
+    #[test]
+    fn consecutive_tab_jumps_collapse_into_one_row() {
+        let jump = |c: char, n: u32| {
+            (
+                key(c),
+                vec![Action::GoToTab(n), Action::SwitchToMode(InputMode::Normal)],
+            )
+        };
+        let mut binds: Vec<_> = (1..=9)
+            .map(|n| jump(char::from_digit(n, 10).unwrap(), n))
+            .collect();
+        binds.push((key('x'), vec![Action::CloseTab]));
+        // A `0` → tab 10 binding, and letters on tabs, keep their own rows.
+        binds.push(jump('0', 10));
+        binds.push(jump('a', 11));
+        binds.push(jump('b', 12));
+        let entries = group_bindings(
+            &binds,
+            InputMode::Tab,
+            InputMode::Normal,
+            &Default::default(),
+            &Grouping::default(),
+        );
+        let rows: Vec<(String, &str)> = entries
+            .iter()
+            .map(|e| (e.keys_str(), e.label.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("x".to_string(), "Close tab"),
+                ("0".to_string(), "Go to tab 10"),
+                ("a".to_string(), "Go to tab 11"),
+                ("b".to_string(), "Go to tab 12"),
+                ("1-9".to_string(), "Go to tab N"),
+            ]
+        );
+        assert!(next_digit_key("Alt+4", "Alt+5"));
+        assert!(!next_digit_key("9", "0"));
+        assert!(!next_digit_key("Alt+1", "Ctrl+2"));
     }
-}
 
-fn format_key(key: &KeyWithModifier) -> String {
-    let mut result = String::new();
-    if key.has_modifiers(&[KeyModifier::Ctrl]) {
-        result.push_str("Ctrl+");
+    #[test]
+    fn directions_fold_into_one_row_per_verb() {
+        let arrow = |k: BareKey| KeyWithModifier::new(k);
+        let binds = vec![
+            (key('l'), vec![Action::MoveFocus(Direction::Right)]),
+            (
+                arrow(BareKey::Left),
+                vec![Action::MoveFocus(Direction::Left)],
+            ),
+            (key('h'), vec![Action::MoveFocus(Direction::Left)]),
+            (key('j'), vec![Action::MoveFocus(Direction::Down)]),
+            (
+                arrow(BareKey::Down),
+                vec![Action::MoveFocus(Direction::Down)],
+            ),
+            (key('k'), vec![Action::MoveFocus(Direction::Up)]),
+            (arrow(BareKey::Up), vec![Action::MoveFocus(Direction::Up)]),
+            (
+                arrow(BareKey::Right),
+                vec![Action::MoveFocus(Direction::Right)],
+            ),
+            // Uneven keys: can't be laid out, so left verbose.
+            (key('H'), vec![Action::MovePane(Some(Direction::Left))]),
+            (key('L'), vec![Action::MovePane(Some(Direction::Right))]),
+            (key('R'), vec![Action::MovePane(Some(Direction::Right))]),
+        ];
+        let mut entries = group_bindings(
+            &binds,
+            InputMode::Pane,
+            InputMode::Normal,
+            &Default::default(),
+            &Grouping::default(),
+        );
+        compact_directions(&mut entries);
+        let rows: Vec<(String, &str)> = entries
+            .iter()
+            .map(|e| (e.keys_str(), e.label.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("h j k l / ←↓↑→".to_string(), "Focus"),
+                ("H".to_string(), "Move pane Left"),
+                ("L R".to_string(), "Move pane Right"),
+            ]
+        );
+        assert_eq!(entries[0].merged.len(), 3);
     }
-    if key.has_modifiers(&[KeyModifier::Alt]) {
-        result.push_str("Alt+");
+
+    #[test]
+    fn previews_nest_under_mode_switches() {
+        let switch = |k: &str, label: &str, m: InputMode| Entry {
+            actions: vec![Action::SwitchToMode(m)],
+            ..entry(&[k], label)
+        };
+        let entries = vec![
+            entry(&["x"], "Close pane"),
+            switch("p", "Pane mode", InputMode::Pane),
+            switch("Esc", "Back to normal", InputMode::Normal),
+        ];
+        let nested = nest_previews(entries, InputMode::Tmux, 2, |m| {
+            assert_eq!(m, InputMode::Pane);
+            vec![
+                entry(&["n"], "New pane"),
+                switch("t", "Tab mode", InputMode::Tab),
+                entry(&["d"], "Split down"),
+                entry(&["r"], "Split right"),
+            ]
+        });
+        let rows: Vec<(String, &str, bool)> = nested
+            .iter()
+            .map(|e| (e.keys_str(), e.label.as_str(), e.nested))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("x".to_string(), "Close pane", false),
+                ("p".to_string(), "Pane mode", false),
+                ("  n".to_string(), "New pane", true),
+                ("  d".to_string(), "Split down", true),
+                ("Esc".to_string(), "Back to normal", false),
+            ]
+        );
     }
-    if key.has_modifiers(&[KeyModifier::Shift]) {
-        result.push_str("Shift+");
+
+    #[test]
+    fn mode_path_follows_switches_and_backtracks() {
+        let mut path = Vec::new();
+        for mode in [InputMode::Tmux, InputMode::Pane, InputMode::Resize] {
+            advance_mode_path(&mut path, mode, InputMode::Normal);
+        }
+        assert_eq!(format_mode_path(&path), "Tmux,Pane,Resize");
+        advance_mode_path(&mut path, InputMode::Pane, InputMode::Normal);
+        assert_eq!(path, vec![InputMode::Tmux, InputMode::Pane]);
+        assert_eq!(parse_mode_path("Tmux,Pane,bogus"), path);
+        advance_mode_path(&mut path, InputMode::Normal, InputMode::Normal);
+        assert!(path.is_empty());
     }
-    match key.bare_key {
-        BareKey::Char(c) => result.push(c),
-        BareKey::F(n) => result.push_str(&format!("F{}", n)),
-        BareKey::Enter => result.push('↵'),
-        BareKey::Esc => result.push_str("Esc"),
-        BareKey::Tab => result.push_str("Tab"),
-        BareKey::Backspace => result.push_str("Bksp"),
-        BareKey::Delete => result.push_str("Del"),
-        BareKey::Insert => result.push_str("Ins"),
-        BareKey::Home => result.push_str("Home"),
-        BareKey::End => result.push_str("End"),
-        BareKey::PageUp => result.push_str("PgUp"),
-        BareKey::PageDown => result.push_str("PgDn"),
-        BareKey::Up => result.push('↑'),
-        BareKey::Down => result.push('↓'),
-        BareKey::Left => result.push('←'),
-        BareKey::Right => result.push('→'),
-        _ => result.push('?'),
+
+    #[test]
+    fn breadcrumb_needs_a_current_path() {
+        let path = [InputMode::Tmux, InputMode::Pane];
+        assert_eq!(breadcrumb(&path, InputMode::Pane), "Tmux › Pane");
+        assert_eq!(previous_mode(&path, InputMode::Pane), Some(InputMode::Tmux));
+        // Stale (the popup's mode moved on) or trivial paths give the heading.
+        assert_eq!(breadcrumb(&path, InputMode::Tab), "Tab mode");
+        assert_eq!(previous_mode(&path, InputMode::Tab), None);
+        assert_eq!(breadcrumb(&path[1..], InputMode::Pane), "Pane mode");
     }
-    result
-}
 
-fn format_action(actions: &[Action], base_mode: InputMode) -> String {
-    let Some(action) = actions.first() else {
-        return "—".to_string();
-    };
-    match action {
-        Action::Quit => "Quit zellij".to_string(),
-        Action::SwitchToMode(mode) => {
-            if *mode == base_mode {
-                "Back to normal".to_string()
-            } else {
-                format!("{:?} mode", mode)
-            }
-        }
-        Action::Resize(resize, dir) => {
-            let verb = match resize {
-                Resize::Increase => "Grow",
-                Resize::Decrease => "Shrink",
-            };
-            match dir {
-                Some(d) => format!("{} {:?}", verb, d),
-                None => verb.to_string(),
-            }
-        }
-        Action::MoveFocus(d) | Action::MoveFocusOrTab(d) => format!("Focus {:?}", d),
-        Action::MovePane(Some(d)) => format!("Move pane {:?}", d),
-        Action::MovePane(None) => "Move pane".to_string(),
-        Action::NewPane(dir, ..) => match dir {
-            Some(d) => format!("New pane {:?}", d),
-            None => "New pane".to_string(),
-        },
-        Action::CloseFocus => "Close pane".to_string(),
-        Action::NewTab(..) => "New tab".to_string(),
-        Action::GoToTab(n) => format!("Go to tab {}", n),
-        Action::GoToNextTab => "Next tab".to_string(),
-        Action::GoToPreviousTab => "Previous tab".to_string(),
-        Action::CloseTab => "Close tab".to_string(),
-        Action::ToggleFloatingPanes => "Toggle floating".to_string(),
-        Action::TogglePaneFrames => "Toggle frames".to_string(),
-        Action::ToggleFocusFullscreen => "Fullscreen".to_string(),
-        Action::ToggleActiveSyncTab => "Sync tab".to_string(),
-        Action::ToggleTab => "Toggle tab".to_string(),
-        Action::PaneNameInput(..) => "Rename pane".to_string(),
-        Action::TabNameInput(..) => "Rename tab".to_string(),
-        Action::UndoRenamePane => "Undo rename".to_string(),
-        Action::UndoRenameTab => "Undo rename".to_string(),
-        Action::HalfPageScrollDown => "Half page down".to_string(),
-        Action::HalfPageScrollUp => "Half page up".to_string(),
-        Action::PageScrollDown => "Page down".to_string(),
-        Action::PageScrollUp => "Page up".to_string(),
-        Action::ScrollDown => "Scroll down".to_string(),
-        Action::ScrollUp => "Scroll up".to_string(),
-        Action::ScrollToBottom => "Scroll to bottom".to_string(),
-        Action::EditScrollback => "Edit scrollback".to_string(),
-        Action::Detach => "Detach".to_string(),
-        Action::Run(..) => "Run command".to_string(),
-        Action::LaunchOrFocusPlugin(..) | Action::LaunchPlugin(..) => "Launch plugin".to_string(),
-        Action::SwitchFocus => "Switch focus".to_string(),
-        Action::MoveTab(d) => format!("Move tab {:?}", d),
-        Action::NewStackedPane(..) => "New stacked pane".to_string(),
-        Action::TogglePaneEmbedOrFloating => "Embed / float pane".to_string(),
-        Action::NextSwapLayout => "Next layout".to_string(),
-        Action::PreviousSwapLayout => "Previous layout".to_string(),
-        Action::BreakPane => "Break pane to new tab".to_string(),
-        Action::BreakPaneRight => "Break pane right".to_string(),
-        Action::BreakPaneLeft => "Break pane left".to_string(),
-        Action::ToggleGroupMarking => "Mark pane group".to_string(),
-        Action::TogglePaneInGroup => "Toggle pane in group".to_string(),
-        other => format!("{:?}", other),
+    #[test]
+    fn strip_flows_entries_across_rows() {
+        // Each "k label" item is 7 wide; rows are 30 wide with " · " between.
+        let entries: Vec<Entry> = "abcdefgh"
+            .chars()
+            .map(|c| entry(&[&c.to_string()], "label"))
+            .collect();
+        let strip = compute_strip(&entries, 0, 0, 1, 36, 3);
+        assert_eq!(strip.width, 30);
+        assert_eq!(strip.rows, vec![0..3, 3..6, 6..8]);
+        assert_eq!(strip.overflow, 0);
+        assert_eq!(strip.pane_rows, 1 + 3 + 2);
     }
-}
 
-fn action_priority(actions: &[Action], _mode: InputMode, base_mode: InputMode) -> u8 {
-    let Some(action) = actions.first() else {
-        return 200;
-    };
-    match action {
-        Action::NewPane(..) | Action::NewTab(..) | Action::NewStackedPane(..) => 10,
-        Action::CloseFocus | Action::CloseTab => 11,
-        Action::MoveFocus(..) | Action::MoveFocusOrTab(..) | Action::SwitchFocus => 12,
-        Action::MovePane(..) => 13,
-        Action::Resize(..) => 14,
-        Action::BreakPane | Action::BreakPaneRight | Action::BreakPaneLeft => 16,
-        Action::GoToTab(..)
-        | Action::GoToNextTab
-        | Action::GoToPreviousTab
-        | Action::ToggleTab
-        | Action::MoveTab(..) => 20,
-        Action::ScrollUp
-        | Action::ScrollDown
-        | Action::PageScrollUp
-        | Action::PageScrollDown
-        | Action::HalfPageScrollUp
-        | Action::HalfPageScrollDown
-        | Action::ScrollToBottom => 25,
-        Action::ToggleFocusFullscreen
-        | Action::ToggleFloatingPanes
-        | Action::TogglePaneFrames
-        | Action::TogglePaneEmbedOrFloating
-        | Action::ToggleActiveSyncTab => 30,
-        Action::NextSwapLayout | Action::PreviousSwapLayout => 32,
-        Action::ToggleGroupMarking | Action::TogglePaneInGroup => 34,
-        Action::PaneNameInput(..)
-        | Action::TabNameInput(..)
-        | Action::UndoRenamePane
-        | Action::UndoRenameTab => 35,
-        Action::Detach => 40,
-        Action::SwitchToMode(mode) if *mode == base_mode => 60,
-        Action::SwitchToMode(..) => 55,
-        Action::Quit => 65,
-        _ => 50,
+    #[test]
+    fn strip_keeps_room_for_the_overflow_note() {
+        let entries: Vec<Entry> = "abcdefgh"
+            .chars()
+            .map(|c| entry(&[&c.to_string()], "label"))
+            .collect();
+        // Two rows: the second gives up an entry to fit "+3 more".
+        let strip = compute_strip(&entries, 0, 0, 1, 36, 2);
+        assert_eq!(strip.rows, vec![0..3, 3..5]);
+        assert_eq!(strip.overflow, 3);
+        // A globals note on a full last row takes another row if allowed.
+        let strip = compute_strip(&entries[..6], 0, 4, 1, 36, 3);
+        assert_eq!(strip.rows, vec![0..3, 3..6, 6..6]);
+        assert_eq!(strip_notes(strip.overflow, 4), vec!["+4 global"]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn strip_pages_pick_up_where_the_last_left_off() {
+        let entries: Vec<Entry> = "abcdefgh"
+            .chars()
+            .map(|c| entry(&[&c.to_string()], "label"))
+            .collect();
+        // Page one holds 0..5 (see above); page two the rest.
+        assert_eq!(strip_pages(&entries, 0, 1, 36, 2), vec![0, 5]);
+        let strip = compute_strip(&entries, 5, 0, 1, 36, 2);
+        assert_eq!(strip.rows, vec![5..8]);
+        assert_eq!(strip.overflow, 0);
+        // Everything fits: a single page.
+        assert_eq!(strip_pages(&entries, 0, 1, 36, 3), vec![0]);
+    }
 
-    fn entry(keys: &[&str], label: &str) -> Entry {
-        Entry {
-            priority: 10,
-            keys: keys.iter().map(|s| s.to_string()).collect(),
-            label: label.to_string(),
-        }
+    #[test]
+    fn base_summary_lists_mode_keys_in_mode_order() {
+        let ctrl = |c: char| KeyWithModifier::new(BareKey::Char(c)).with_ctrl_modifier();
+        let switch = |m: InputMode| vec![Action::SwitchToMode(m)];
+        let binds = vec![
+            (ctrl('n'), switch(InputMode::Resize)),
+            (ctrl('p'), switch(InputMode::Pane)),
+            (ctrl('q'), vec![Action::Quit]),
+            (ctrl('t'), switch(InputMode::Tab)),
+            (key('P'), switch(InputMode::Pane)),
+            (key('n'), switch(InputMode::Normal)),
+        ];
+        assert_eq!(
+            base_summary(&binds, InputMode::Normal),
+            "Ctrl+p/P Pane · Ctrl+t Tab · Ctrl+n Resize"
+        );
     }
 
     #[test]
-    fn display_width_counts_chars_not_bytes() {
-        assert_eq!(display_width("abc"), 3);
-        // Arrows are 3 bytes each but one column wide.
-        assert_eq!(display_width("← ↓ ↑ →"), 7);
+    fn summary_box_wraps_to_the_display() {
+        let summary = "Ctrl+p Pane · Ctrl+t Tab · Ctrl+n Resize";
+        let (cols, rows, lines) = summary_box(summary, 1, 200, 50);
+        assert_eq!((cols, rows, lines.len()), (44, 4, 1));
+        let (cols, rows, lines) = summary_box(summary, 1, 30, 50);
+        assert_eq!(cols, 28);
+        assert_eq!(rows, 1 + lines.len() + 2);
+        assert!(lines.iter().all(|l| display_width(l) <= 24));
     }
 
     #[test]
-    fn truncate_never_splits_a_multibyte_char() {
-        // The bug that used to panic: byte-slicing through a multibyte char.
-        let s = "→ Resize mode that is quite long";
-        for max in 0..=display_width(s) + 2 {
-            let out = truncate_to_width(s, max);
-            assert!(out.chars().count() <= max.max(1));
-            // Must always be valid UTF-8 / not panic, and round-trip as chars.
-            assert_eq!(out, out.chars().collect::<String>());
-        }
+    fn key_ranges_share_the_modifier_prefix() {
+        assert_eq!(key_range("1", "9"), "1-9");
+        assert_eq!(key_range("Alt+1", "Alt+5"), "Alt+1-5");
     }
 
     #[test]
-    fn truncate_adds_ellipsis_when_too_long() {
-        assert_eq!(truncate_to_width("abcdef", 4), "abc…");
-        assert_eq!(truncate_to_width("abc", 4), "abc");
-        assert_eq!(truncate_to_width("abc", 0), "");
+    fn tab_list_numbers_tabs_by_position() {
+        let tab = |position: usize, name: &str| TabInfo {
+            position,
+            name: name.to_string(),
+            ..TabInfo::default()
+        };
+        assert_eq!(
+            tab_list(&[tab(1, "logs"), tab(0, "editor")]),
+            "1 editor · 2 logs"
+        );
     }
 
     #[test]
-    fn pad_right_pads_to_width_and_never_truncates() {
-        assert_eq!(pad_right("ab", 4), "ab  ");
-        assert_eq!(pad_right("abcd", 2), "abcd");
-        assert_eq!(display_width(&pad_right("←", 3)), 3);
+    fn variant_name_drops_the_payload() {
+        assert_eq!(
+            action_variant(&Action::NewPane(None, None, false)),
+            "NewPane"
+        );
+        assert_eq!(action_variant(&Action::CloseFocus), "CloseFocus");
     }
 
     #[test]
     fn layout_shows_everything_when_it_fits() {
         let entries = vec![entry(&["h"], "Focus Left"), entry(&["l"], "Focus Right")];
-        let layout = compute_layout(&entries, 120, 40, 40);
+        let layout = compute_layout(&entries, &[], 1, 120, 40, 40);
         assert_eq!(layout.visible, 2);
         assert_eq!(layout.overflow, 0);
     }
@@ -1050,7 +5593,7 @@ mod tests {
         let entries: Vec<Entry> = (0..50)
             .map(|i| entry(&["x"], &format!("Action {}", i)))
             .collect();
-        let layout = compute_layout(&entries, 120, 30, 40);
+        let layout = compute_layout(&entries, &[], 1, 120, 30, 40);
         assert!(layout.visible >= 1);
         assert!(layout.overflow > 0);
         assert_eq!(layout.visible + layout.overflow, 50);
@@ -1059,10 +5602,35 @@ mod tests {
     #[test]
     fn layout_width_respects_the_display_and_caps() {
         let entries = vec![entry(&["h"], "A short label")];
-        let narrow = compute_layout(&entries, 20, 40, 40);
+        let narrow = compute_layout(&entries, &[], 1, 20, 40, 40);
         assert!(narrow.pane_cols <= 20);
     }
 
+    #[test]
+    fn layout_shows_the_globals_section_when_it_fits() {
+        let entries = vec![entry(&["n"], "New pane")];
+        let globals = vec![
+            entry(&["Alt+h"], "Focus Left"),
+            entry(&["Alt+l"], "Focus Right"),
+        ];
+        let layout = compute_layout(&entries, &globals, 1, 120, 40, 40);
+        assert!(layout.globals_shown);
+        // Header, one entry, the "Global" heading and two globals, plus border.
+        assert_eq!(layout.pane_rows, 1 + 1 + 3 + 2);
+    }
+
+    #[test]
+    fn layout_collapses_globals_when_tight() {
+        let entries: Vec<Entry> = (0..6).map(|i| entry(&["x"], &format!("A {}", i))).collect();
+        let globals: Vec<Entry> = (0..6).map(|i| entry(&["y"], &format!("G {}", i))).collect();
+        // 30% of 30 rows = 9, minus border and header = 6 body rows.
+        let layout = compute_layout(&entries, &globals, 1, 120, 30, 30);
+        assert!(!layout.globals_shown);
+        assert_eq!(layout.visible + layout.overflow, 6);
+        assert_eq!(layout.visible, 4);
+        assert_eq!(layout.pane_rows, 9);
+    }
+
     #[test]
     fn keys_string_joins_with_spaces() {
         assert_eq!(entry(&["h", "←"], "Focus Left").keys_str(), "h ←");